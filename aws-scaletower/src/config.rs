@@ -6,7 +6,10 @@ use failure::Error;
 use serde_derive::{Deserialize, Serialize};
 
 use aws::{kms, AwsClientConfig};
-use lambda::config::{BosunConfig, EncryptedConfig};
+use lambda::{
+    asg_mapping::Mappings,
+    config::{BosunConfig, EncryptedConfig, MetricSinkConfig},
+};
 
 #[derive(Config, PartialEq, Deserialize, Serialize, Debug)]
 pub struct EncryptedFunctionConfig {
@@ -18,8 +21,11 @@ impl EncryptedConfig<EncryptedFunctionConfig, FunctionConfig> for EncryptedFunct
     fn decrypt(self, aws_client_config: &AwsClientConfig) -> Result<FunctionConfig, Error> {
         let bosun_auth_password = kms::decrypt_base64(aws_client_config, &self.bosun.password)?;
 
+        let bosun_tls = self.bosun.tls.map(|tls| tls.decrypt(aws_client_config)).transpose()?;
+
         let bosun = BosunConfig {
             password: bosun_auth_password,
+            tls: bosun_tls,
             ..self.bosun
         };
 
@@ -48,6 +54,12 @@ impl Default for FunctionConfig {
             password: "bosun".to_string(),
             timeout: Some(5),
             tags: HashMap::new(),
+            retries: None,
+            spool_file: None,
+            sink: MetricSinkConfig::Bosun,
+            connect_timeout: None,
+            proxy: None,
+            tls: None,
         };
 
         let burst_balance = BurstBalanceConfig {
@@ -57,6 +69,8 @@ impl Default for FunctionConfig {
             burst_balance_limit: 10.0,
             eta_limit_min: 10,
             terminate: false,
+            max_alerting_hosts: None,
+            mappings: Mappings::default(),
         };
 
         FunctionConfig { bosun, burst_balance }
@@ -71,6 +85,13 @@ pub struct BurstBalanceConfig {
     pub burst_balance_limit: f64,
     pub eta_limit_min: i64,
     pub terminate: bool,
+    /// Skips termination of an instance if Bosun sees more alerting hosts in its ASG, because this points to a wider
+    /// outage.
+    #[serde(default)]
+    pub max_alerting_hosts: Option<usize>,
+    /// Maps ASG names to the tags Bosun knows their hosts by, cf. aws-watchtower; required to check for a wider outage.
+    #[serde(default)]
+    pub mappings: Mappings,
 }
 
 #[cfg(test)]
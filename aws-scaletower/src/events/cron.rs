@@ -1,11 +1,12 @@
 use chrono::Utc;
 use failure::Error;
 use lambda_runtime::Context;
-use log::{debug, info};
+use log::{debug, info, warn};
 use serde_derive::Deserialize;
 
-use aws::{AwsClientConfig, Filter};
-use bosun::{Bosun, Datum, Tags};
+use aws::{ec2::asg::get_asg_by_instance_id, AwsClientConfig, Filter};
+use bosun::{incident::alerting_hosts, Bosun, Incident, Tags};
+use lambda::asg_mapping::Mappings;
 
 use crate::burst_balance::{get_burst_balances, BurstBalance};
 use crate::config::{BurstBalanceConfig, FunctionConfig};
@@ -38,13 +39,13 @@ pub struct ScheduledEvent {
 
 pub fn handle<T: Bosun>(
     aws_client_config: &AwsClientConfig,
-    _: &Context,
+    ctx: &Context,
     config: &FunctionConfig,
     bosun: &T,
 ) -> Result<HandleResult, Error> {
     info!("Received Scheduled Event.");
 
-    let burst_balance = burst_balance(aws_client_config, &config.burst_balance, bosun)?;
+    let burst_balance = burst_balance(aws_client_config, ctx, &config.burst_balance, bosun)?;
 
     let handle_result = HandleResult::Cron { burst_balance };
 
@@ -53,6 +54,7 @@ pub fn handle<T: Bosun>(
 
 pub fn burst_balance<T: Bosun>(
     aws_client_config: &AwsClientConfig,
+    ctx: &Context,
     config: &BurstBalanceConfig,
     bosun: &T,
 ) -> Result<usize, Error> {
@@ -93,9 +95,25 @@ pub fn burst_balance<T: Bosun>(
 
     if !instances.is_empty() {
         if config.terminate {
-            aws::ec2::ec2::terminate_instances(aws_client_config, instances.clone())?;
-            info!("Terminated {} instances: '{:?}'", instances.len(), instances);
-            bosun_emit_terminated(bosun, instances.len())?;
+            let incidents = open_incidents_for_safety_check(bosun, config)?;
+            let mut terminate = Vec::new();
+            for instance_id in &instances {
+                let scope = OutageScope::of(aws_client_config, config, instance_id)?;
+                if is_wider_outage(config, &incidents, &scope) {
+                    warn!(
+                        "Skipping termination of instance '{}', because Bosun indicates a wider outage.",
+                        instance_id
+                    );
+                } else {
+                    terminate.push(instance_id.clone());
+                }
+            }
+            if !terminate.is_empty() {
+                aws::ec2::ec2::terminate_instances(aws_client_config, terminate.clone())?;
+                info!("Terminated {} instances: '{:?}'", terminate.len(), terminate);
+                annotate_terminated(bosun, ctx, &terminate);
+                bosun_emit_terminated(bosun, terminate.len())?;
+            }
         } else {
             info!("Would have terminated {} instances: '{:?}'", instances.len(), instances);
         }
@@ -106,6 +124,108 @@ pub fn burst_balance<T: Bosun>(
     Ok(instances.len())
 }
 
+/// Bosun tags a termination candidate is known by: its host and, if it belongs to a mapped ASG, its ASG.
+///
+/// Both are derived from the ASG mappings the same way aws-watchtower tags hosts and metrics.
+#[derive(Debug)]
+struct OutageScope {
+    /// Host name prefix of the candidate, i.e., the mapped host prefix followed by the instance id
+    host: String,
+    /// Mapped `asg` tag of the candidate's ASG
+    asg: Option<String>,
+}
+
+impl OutageScope {
+    fn of(aws_client_config: &AwsClientConfig, config: &BurstBalanceConfig, instance_id: &str) -> Result<Self, Error> {
+        // Only ask AWS for the ASG if the safety check is enabled at all.
+        let asg_name = if config.max_alerting_hosts.is_some() {
+            get_asg_by_instance_id(aws_client_config, instance_id.to_string())?.map(|x| x.auto_scaling_group_name)
+        } else {
+            None
+        };
+
+        Ok(OutageScope::new(&config.mappings, instance_id, asg_name.as_deref()))
+    }
+
+    fn new(mappings: &Mappings, instance_id: &str, asg_name: Option<&str>) -> Self {
+        let mapping = asg_name.and_then(|x| mappings.map(x));
+        if asg_name.is_some() && mapping.is_none() {
+            info!(
+                "No mapping found for ASG '{:?}' of instance '{}'.",
+                asg_name, instance_id
+            );
+        }
+
+        OutageScope {
+            host: format!(
+                "{}{}",
+                mapping.map(|x| x.host_prefix.as_str()).unwrap_or(""),
+                instance_id
+            ),
+            asg: mapping.map(|x| x.tag_name.clone()),
+        }
+    }
+
+    fn asg_tags(&self) -> Option<Tags> {
+        self.asg.as_ref().map(|asg| {
+            let mut tags = Tags::new();
+            tags.insert("asg".to_string(), asg.clone());
+            tags
+        })
+    }
+
+    /// Checks if `host` denotes the candidate; like aws-watchtower's silences, this allows for a suffix.
+    fn is_host(&self, host: &str) -> bool {
+        host.starts_with(&self.host)
+    }
+}
+
+/// Fetches the open incidents once per run, if the safety check is enabled at all.
+fn open_incidents_for_safety_check<T: Bosun>(bosun: &T, config: &BurstBalanceConfig) -> Result<Vec<Incident>, Error> {
+    if config.max_alerting_hosts.is_none() {
+        return Ok(Vec::new());
+    }
+    let incidents = bosun.open_incidents()?;
+    debug!("Retrieved {} open incidents for safety check.", incidents.len());
+
+    Ok(incidents)
+}
+
+/// Checks if Bosun sees more alerting hosts than `max_alerting_hosts` in the ASG of the termination candidate;
+/// terminating the candidate would make it worse. The candidate itself does not count, because it is expected to
+/// alert while running out of burst balance.
+fn is_wider_outage(config: &BurstBalanceConfig, incidents: &[Incident], scope: &OutageScope) -> bool {
+    let max_alerting_hosts = match config.max_alerting_hosts {
+        Some(max_alerting_hosts) => max_alerting_hosts,
+        None => return false,
+    };
+    let asg_tags = match scope.asg_tags() {
+        Some(asg_tags) => asg_tags,
+        None => {
+            info!(
+                "Host '{}' does not belong to a mapped ASG, so there is no wider outage to check.",
+                scope.host
+            );
+            return false;
+        }
+    };
+
+    let incidents: Vec<_> = incidents.iter().filter(|x| x.has_tags(&asg_tags)).cloned().collect();
+    let alerting_hosts: Vec<_> = alerting_hosts(&incidents)
+        .into_iter()
+        .filter(|x| !scope.is_host(x))
+        .collect();
+    info!(
+        "Bosun sees {} other alerting hosts in ASG '{:?}' (limit: {}): '{:?}'",
+        alerting_hosts.len(),
+        scope.asg,
+        max_alerting_hosts,
+        alerting_hosts
+    );
+
+    alerting_hosts.len() > max_alerting_hosts
+}
+
 trait IsExhausted {
     fn is_exhausted(&self, config: &BurstBalanceConfig) -> bool;
 }
@@ -132,7 +252,7 @@ impl IsExhausted for BurstBalance {
 fn bosun_emit_candidates<T: Bosun>(bosun: &T, value: usize) -> Result<(), Error> {
     let tags = Tags::new();
     let value = value.to_string();
-    let datum = Datum::now(metrics::BURST_BALANCE_TERMINATION_CANDIDATES, &value, &tags);
+    let datum = metrics::METRICS.datum_now(metrics::BURST_BALANCE_TERMINATION_CANDIDATES, &value, &tags)?;
     bosun.emit_datum(&datum)?;
 
     Ok(())
@@ -141,15 +261,27 @@ fn bosun_emit_candidates<T: Bosun>(bosun: &T, value: usize) -> Result<(), Error>
 fn bosun_emit_terminated<T: Bosun>(bosun: &T, value: usize) -> Result<(), Error> {
     let tags = Tags::new();
     let value = value.to_string();
-    let datum = Datum::now(metrics::BURST_BALANCE_TERMINATION_TERMINATED, &value, &tags);
+    let datum = metrics::METRICS.datum_now(metrics::BURST_BALANCE_TERMINATION_TERMINATED, &value, &tags)?;
     bosun.emit_datum(&datum)?;
 
     Ok(())
 }
 
+fn annotate_terminated<T: Bosun>(bosun: &T, ctx: &Context, instances: &[String]) {
+    for instance_id in instances {
+        let message = format!("Terminated instance {} due to exhausted burst balance", instance_id);
+        lambda::bosun::annotate_action(bosun, ctx, "burst_balance.termination", Some(instance_id), &message);
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use bosun::{
+        testing::{BosunMockClient, Call},
+        AlertStatus, BosunError, Incident,
+    };
     use chrono::Duration;
+    use lambda::asg_mapping::Mapping;
     use serde_json::json;
     use spectral::prelude::*;
 
@@ -195,6 +327,8 @@ mod tests {
             burst_balance_limit: 10.0,
             eta_limit_min: 10,
             terminate: false,
+            max_alerting_hosts: None,
+            mappings: Mappings::default(),
         };
         let burst_balance = BurstBalance {
             volume_id: "vol-123".to_string(),
@@ -218,6 +352,8 @@ mod tests {
             burst_balance_limit: 10.0,
             eta_limit_min: 10,
             terminate: false,
+            max_alerting_hosts: None,
+            mappings: Mappings::default(),
         };
         let burst_balance = BurstBalance {
             volume_id: "vol-123".to_string(),
@@ -241,6 +377,8 @@ mod tests {
             burst_balance_limit: 10.0,
             eta_limit_min: 10,
             terminate: false,
+            max_alerting_hosts: None,
+            mappings: Mappings::default(),
         };
         let burst_balance = BurstBalance {
             volume_id: "vol-123".to_string(),
@@ -264,6 +402,8 @@ mod tests {
             burst_balance_limit: 10.0,
             eta_limit_min: 10,
             terminate: false,
+            max_alerting_hosts: None,
+            mappings: Mappings::default(),
         };
         let burst_balance = BurstBalance {
             volume_id: "vol-123".to_string(),
@@ -277,4 +417,211 @@ mod tests {
 
         assert_that(&res).is_true();
     }
+
+    fn incident(id: i64, host: &str, asg: &str, status: AlertStatus) -> Incident {
+        let mut tags = Tags::new();
+        tags.insert("host".to_string(), host.to_string());
+        tags.insert("asg".to_string(), asg.to_string());
+        Incident {
+            id,
+            subject: String::new(),
+            start: 1_602_842_400,
+            alert_name: "os.cpu.high".to_string(),
+            tags,
+            current_status: status,
+            worst_status: status,
+            unevaluated: false,
+            need_ack: true,
+            silenced: false,
+        }
+    }
+
+    fn config_with_max_alerting_hosts(max_alerting_hosts: Option<usize>) -> BurstBalanceConfig {
+        BurstBalanceConfig {
+            instance_name_filter: "not-relevant".to_string(),
+            look_back_min: 0,
+            use_linear_regression: false,
+            burst_balance_limit: 10.0,
+            eta_limit_min: 10,
+            terminate: true,
+            max_alerting_hosts,
+            mappings: mappings(),
+        }
+    }
+
+    fn mappings() -> Mappings {
+        let items = vec![Mapping {
+            search: "webserver".to_string(),
+            tag_name: "webserver".to_string(),
+            host_prefix: "webserver-".to_string(),
+        }];
+        Mappings { items }
+    }
+
+    fn scope(instance_id: &str, asg_name: Option<&str>) -> OutageScope {
+        OutageScope::new(&mappings(), instance_id, asg_name)
+    }
+
+    #[test]
+    fn scope_uses_asg_mapping() {
+        setup();
+
+        let mapped = scope("i-1", Some("project-staging-asg-webserver-20181205092547277600000001"));
+        let unmapped = scope("i-1", Some("project-staging-asg-database-20181205092547277600000001"));
+
+        assert_that(&mapped.host.as_str()).is_equal_to("webserver-i-1");
+        assert_that(&mapped.asg).is_equal_to(Some("webserver".to_string()));
+        assert_that(&unmapped.host.as_str()).is_equal_to("i-1");
+        assert_that(&unmapped.asg).is_none();
+    }
+
+    #[test]
+    fn wider_outage_if_too_many_hosts_of_asg_alert() {
+        setup();
+
+        let incidents = vec![
+            incident(1, "webserver-i-1", "webserver", AlertStatus::Critical),
+            incident(2, "webserver-i-2", "webserver", AlertStatus::Critical),
+            incident(3, "webserver-i-2", "webserver", AlertStatus::Warning),
+            incident(4, "webserver-i-3", "webserver", AlertStatus::Warning),
+            incident(5, "webserver-i-4", "webserver", AlertStatus::Normal),
+        ];
+
+        let res = is_wider_outage(
+            &config_with_max_alerting_hosts(Some(1)),
+            &incidents,
+            &scope("i-1", Some("asg-webserver")),
+        );
+
+        assert_that(&res).is_true();
+    }
+
+    #[test]
+    fn no_wider_outage_if_only_candidate_alerts() {
+        setup();
+
+        let incidents = vec![
+            incident(1, "webserver-i-1", "webserver", AlertStatus::Critical),
+            incident(2, "webserver-i-1.example.com", "webserver", AlertStatus::Warning),
+            incident(3, "webserver-i-2", "webserver", AlertStatus::Normal),
+        ];
+
+        let res = is_wider_outage(
+            &config_with_max_alerting_hosts(Some(0)),
+            &incidents,
+            &scope("i-1", Some("asg-webserver")),
+        );
+
+        assert_that(&res).is_false();
+    }
+
+    #[test]
+    fn no_wider_outage_if_other_asgs_alert() {
+        setup();
+
+        let incidents = vec![
+            incident(1, "database-i-2", "database", AlertStatus::Critical),
+            incident(2, "database-i-3", "database", AlertStatus::Critical),
+            incident(3, "webserver-i-4", "webserver", AlertStatus::Warning),
+        ];
+
+        let res = is_wider_outage(
+            &config_with_max_alerting_hosts(Some(1)),
+            &incidents,
+            &scope("i-1", Some("asg-webserver")),
+        );
+
+        assert_that(&res).is_false();
+    }
+
+    #[test]
+    fn no_wider_outage_without_mapped_asg() {
+        setup();
+
+        let incidents = vec![
+            incident(1, "webserver-i-2", "webserver", AlertStatus::Critical),
+            incident(2, "webserver-i-3", "webserver", AlertStatus::Critical),
+        ];
+        let config = config_with_max_alerting_hosts(Some(0));
+
+        let without_asg = is_wider_outage(&config, &incidents, &scope("i-1", None));
+        let unmapped_asg = is_wider_outage(&config, &incidents, &scope("i-1", Some("asg-database")));
+
+        assert_that(&without_asg).is_false();
+        assert_that(&unmapped_asg).is_false();
+    }
+
+    #[test]
+    fn safety_check_disabled_does_not_query_bosun() {
+        setup();
+
+        let bosun = BosunMockClient::default();
+
+        let res = open_incidents_for_safety_check(&bosun, &config_with_max_alerting_hosts(None));
+
+        assert_that(&res).is_ok().is_empty();
+        assert_that(&bosun.calls(Call::OpenIncidents)).is_equal_to(0);
+    }
+
+    #[test]
+    fn safety_check_queries_bosun_once() {
+        setup();
+
+        let bosun =
+            BosunMockClient::with_incidents(vec![incident(1, "webserver-i-2", "webserver", AlertStatus::Critical)]);
+
+        let res = open_incidents_for_safety_check(&bosun, &config_with_max_alerting_hosts(Some(0)));
+
+        assert_that(&res).is_ok().has_length(1);
+        assert_that(&bosun.calls(Call::OpenIncidents)).is_equal_to(1);
+    }
+
+    #[test]
+    fn emit_candidates_and_terminated() {
+        setup();
+
+        let bosun = BosunMockClient::default();
+
+        let candidates = bosun_emit_candidates(&bosun, 3);
+        let terminated = bosun_emit_terminated(&bosun, 2);
+
+        assert_that(&candidates).is_ok();
+        assert_that(&terminated).is_ok();
+        let candidates = bosun.data_for(metrics::BURST_BALANCE_TERMINATION_CANDIDATES);
+        assert_that(&candidates).has_length(1);
+        assert_that(&candidates[0].value.as_str()).is_equal_to("3");
+        let terminated = bosun.data_for(metrics::BURST_BALANCE_TERMINATION_TERMINATED);
+        assert_that(&terminated).has_length(1);
+        assert_that(&terminated[0].value.as_str()).is_equal_to("2");
+    }
+
+    #[test]
+    fn annotate_every_terminated_instance() {
+        setup();
+
+        let bosun = BosunMockClient::default();
+        let ctx = Context::default();
+        let instances = vec!["i-123".to_string(), "i-456".to_string()];
+
+        annotate_terminated(&bosun, &ctx, &instances);
+
+        let annotations = bosun.annotations_for("burst_balance.termination");
+        assert_that(&annotations).has_length(2);
+        assert_that(&annotations[1].host.as_str()).is_equal_to("i-456");
+    }
+
+    #[test]
+    fn emit_candidates_fails_if_bosun_fails() {
+        setup();
+
+        let bosun = BosunMockClient::default().fail_always(
+            Call::Datum,
+            BosunError::ReceiveError("500 Internal Server Error".to_string()),
+        );
+
+        let res = bosun_emit_candidates(&bosun, 3);
+
+        assert_that(&res).is_err();
+        assert_that(&bosun.data_for(metrics::BURST_BALANCE_TERMINATION_CANDIDATES)).is_empty();
+    }
 }
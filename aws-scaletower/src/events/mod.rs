@@ -1,9 +1,9 @@
 use crate::{config::FunctionConfig, error::AwsScaleTowerError};
 use aws::AwsClientConfig;
-use bosun::{Bosun, Datum, Tags};
+use bosun::{Bosun, Tags};
 use failure::{Error, Fail};
 use lambda_runtime::Context;
-use log::debug;
+use log::{debug, warn};
 use serde_derive::{Deserialize, Serialize};
 use serde_json::{self, Value};
 
@@ -39,20 +39,21 @@ pub fn handle<T: Bosun>(
     bosun: &T,
 ) -> Result<HandleResult, Error> {
     let tags = Tags::new();
-    let datum = Datum::now(lambda::metrics::LAMBDA_INVOCATION_COUNT, "1", &tags);
-    bosun.emit_datum(&datum)?;
+    let datum = lambda::metrics::METRICS.datum_now(lambda::metrics::LAMBDA_INVOCATION_COUNT, "1", &tags);
+    // A monitoring outage must not keep us from handling the event.
+    if let Err(err) = datum.and_then(|datum| bosun.emit_datum(&datum)) {
+        warn!("Failed to emit invocation count because {}.", err);
+    }
 
     let res = parse_event(json).and_then(|event| handle_event(aws_client_config, event, ctx, &config, bosun));
 
-    match res {
-        Ok(_) => {
-            let datum = Datum::now(lambda::metrics::LAMBDA_INVOCATION_RESULT, "0", &tags);
-            bosun.emit_datum(&datum)?
-        }
-        Err(_) => {
-            let datum = Datum::now(lambda::metrics::LAMBDA_INVOCATION_RESULT, "1", &tags);
-            bosun.emit_datum(&datum)?
-        }
+    let value = match res {
+        Ok(_) => "0",
+        Err(_) => "1",
+    };
+    let datum = lambda::metrics::METRICS.datum_now(lambda::metrics::LAMBDA_INVOCATION_RESULT, value, &tags);
+    if let Err(err) = datum.and_then(|datum| bosun.emit_datum(&datum)) {
+        warn!("Failed to emit invocation result because {}.", err);
     }
 
     res
@@ -78,3 +79,83 @@ fn handle_event<T: Bosun>(
         Event::Ping(ping) => ping::handle(ping, ctx, config, bosun),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use bosun::{
+        testing::{BosunMockClient, Call},
+        BosunError,
+    };
+    use lambda::metrics::{LAMBDA_INVOCATION_COUNT, LAMBDA_INVOCATION_RESULT};
+
+    use serde_json::json;
+    use spectral::prelude::*;
+    use testing::setup;
+
+    #[test]
+    fn test_handle_ping() {
+        setup();
+
+        let aws_client_config = AwsClientConfig::new().expect("Failed to create AWS client config.");
+        let bosun: BosunMockClient = Default::default();
+        let ctx = Context::default();
+        let config = FunctionConfig::default();
+        let event = json!(
+            { "source": "ping", "ping": "echo request" }
+        );
+
+        let res = handle(&aws_client_config, event, &ctx, &config, &bosun);
+        assert_that!(&res).is_ok();
+
+        assert_that(&bosun.data_for(LAMBDA_INVOCATION_COUNT)).has_length(1);
+        let result = bosun.data_for(LAMBDA_INVOCATION_RESULT);
+        assert_that(&result).has_length(1);
+        assert_that(&result[0].value.as_str()).is_equal_to("0");
+    }
+
+    #[test]
+    fn test_handle_unparsable_event_emits_failure_result() {
+        setup();
+
+        let aws_client_config = AwsClientConfig::new().expect("Failed to create AWS client config.");
+        let bosun: BosunMockClient = Default::default();
+        let ctx = Context::default();
+        let config = FunctionConfig::default();
+        let event = json!(
+            { "this": "object", "does": "not parse" }
+        );
+
+        let res = handle(&aws_client_config, event, &ctx, &config, &bosun);
+        assert_that!(&res).is_err();
+
+        let result = bosun.data_for(LAMBDA_INVOCATION_RESULT);
+        assert_that(&result).has_length(1);
+        assert_that(&result[0].value.as_str()).is_equal_to("1");
+    }
+
+    #[test]
+    fn test_handle_ping_with_bosun_unavailable() {
+        setup();
+
+        let aws_client_config = AwsClientConfig::new().expect("Failed to create AWS client config.");
+        let bosun = BosunMockClient::default().fail_nth(
+            Call::Datum,
+            1,
+            BosunError::Unavailable("503 Service Unavailable".to_string()),
+        );
+        let ctx = Context::default();
+        let config = FunctionConfig::default();
+        let event = json!(
+            { "source": "ping", "ping": "echo request" }
+        );
+
+        let res = handle(&aws_client_config, event, &ctx, &config, &bosun);
+        assert_that!(&res).is_ok();
+
+        assert_that(&bosun.calls(Call::Datum)).is_equal_to(2);
+        assert_that(&bosun.data_for(LAMBDA_INVOCATION_COUNT)).is_empty();
+        assert_that(&bosun.data_for(LAMBDA_INVOCATION_RESULT)).has_length(1);
+    }
+}
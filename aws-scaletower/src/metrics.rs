@@ -1,33 +1,32 @@
-use bosun::{Bosun, Metadata};
+use bosun::{registry::MetricRegistry, Bosun, Rate, Unit};
 use failure::Error;
 
 pub static BURST_BALANCE_TERMINATION_CANDIDATES: &str = "aws.burst_balance.termination.candidates";
 pub static BURST_BALANCE_TERMINATION_TERMINATED: &str = "aws.burst_balance.termination.terminated";
 pub static SCHEDULED_EVENT: &str = "aws.events.scheduled_event";
 
-pub fn send_metadata<T: Bosun>(bosun: &T) -> Result<(), Error> {
-    let metadatas = bosun_metadata();
-    lambda::bosun::send_metadata(bosun, &metadatas)
+lazy_static::lazy_static! {
+    pub static ref METRICS: MetricRegistry = MetricRegistry::new()
+        .register(
+            BURST_BALANCE_TERMINATION_CANDIDATES,
+            Rate::Gauge,
+            Unit::Instances,
+            "Number of candidate instances to terminate because of burst balance exhaustion",
+        )
+        .register(
+            BURST_BALANCE_TERMINATION_TERMINATED,
+            Rate::Gauge,
+            Unit::Instances,
+            "Number of instances terminated because of burst balance exhaustion",
+        )
+        .register(
+            SCHEDULED_EVENT,
+            Rate::Gauge,
+            Unit::Event,
+            "AWS schedule event",
+        );
 }
 
-fn bosun_metadata() -> Vec<Metadata<'static>> {
-    let mut metadatas = Vec::new();
-
-    metadatas.push(Metadata::new(
-        BURST_BALANCE_TERMINATION_CANDIDATES,
-        "gauge",
-        "Instances",
-        "Number of candidate instances to terminate because of burst balance exhaustion",
-    ));
-
-    metadatas.push(Metadata::new(
-        BURST_BALANCE_TERMINATION_TERMINATED,
-        "gauge",
-        "Instances",
-        "Number of instances terminated because of burst balance exhaustion",
-    ));
-
-    metadatas.push(Metadata::new(SCHEDULED_EVENT, "gauge", "Event", "AWS schedule event"));
-
-    metadatas
+pub fn send_metadata<T: Bosun>(bosun: &T) -> Result<(), Error> {
+    lambda::bosun::send_metadata(bosun, &METRICS)
 }
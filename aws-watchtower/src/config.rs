@@ -6,7 +6,7 @@ use failure::Error;
 use serde_derive::{Deserialize, Serialize};
 
 use aws::{kms, AwsClientConfig};
-use lambda::config::{BosunConfig, EncryptedConfig};
+use lambda::config::{BosunConfig, EncryptedConfig, MetricSinkConfig};
 
 use crate::asg_mapping::Mappings;
 
@@ -21,8 +21,11 @@ impl EncryptedConfig<EncryptedFunctionConfig, FunctionConfig> for EncryptedFunct
     fn decrypt(self, aws_client_config: &AwsClientConfig) -> Result<FunctionConfig, Error> {
         let bosun_auth_password = kms::decrypt_base64(aws_client_config, &self.bosun.password)?;
 
+        let bosun_tls = self.bosun.tls.map(|tls| tls.decrypt(aws_client_config)).transpose()?;
+
         let bosun = BosunConfig {
             password: bosun_auth_password,
+            tls: bosun_tls,
             ..self.bosun
         };
 
@@ -39,12 +42,32 @@ impl EncryptedConfig<EncryptedFunctionConfig, FunctionConfig> for EncryptedFunct
 #[derive(PartialEq, Deserialize, Serialize, Debug)]
 pub struct Asg {
     pub scaledown_silence_duration: String,
+    #[serde(default = "default_silence_user")]
+    pub scaledown_silence_user: String,
+    #[serde(default = "default_asg_silence_message")]
+    pub scaledown_silence_message: String,
     pub mappings: Mappings,
 }
 
 #[derive(PartialEq, Deserialize, Serialize, Debug)]
 pub struct Ec2 {
     pub scaledown_silence_duration: String,
+    #[serde(default = "default_silence_user")]
+    pub scaledown_silence_user: String,
+    #[serde(default = "default_ec2_silence_message")]
+    pub scaledown_silence_message: String,
+}
+
+fn default_silence_user() -> String {
+    "kevin.lambda".to_string()
+}
+
+fn default_asg_silence_message() -> String {
+    "Host has been terminated by ASG.".to_string()
+}
+
+fn default_ec2_silence_message() -> String {
+    "Host is shutting down and may be terminated by ASG.".to_string()
 }
 
 #[derive(PartialEq, Deserialize, Serialize, Debug)]
@@ -64,15 +87,25 @@ impl Default for FunctionConfig {
             password: "bosun".to_string(),
             timeout: Some(5),
             tags: HashMap::new(),
+            retries: None,
+            spool_file: None,
+            sink: MetricSinkConfig::Bosun,
+            connect_timeout: None,
+            proxy: None,
+            tls: None,
         };
 
         let asg = Asg {
             scaledown_silence_duration: "24h".to_string(),
+            scaledown_silence_user: default_silence_user(),
+            scaledown_silence_message: default_asg_silence_message(),
             mappings: Mappings { items: Vec::new() },
         };
 
         let ec2 = Ec2 {
             scaledown_silence_duration: "15m".to_string(),
+            scaledown_silence_user: default_silence_user(),
+            scaledown_silence_message: default_ec2_silence_message(),
         };
 
         FunctionConfig { bosun, asg, ec2 }
@@ -141,4 +174,41 @@ scaledown_silence_duration = "15m"
             .is_ok()
             .is_equal_to(&expected);
     }
+
+    #[test]
+    fn deserialize_function_config_with_silence_user_and_message() {
+        let toml = r#"[bosun]
+host = 'localhost:8070'
+user = 'bosun'
+password = 'bosun'
+timeout = 5
+
+[bosun.tags]
+
+[asg]
+scaledown_silence_duration = "24h"
+scaledown_silence_user = "ops.lambda"
+scaledown_silence_message = "Scaled down by ASG."
+
+[asg.mappings]
+mapping = []
+
+[ec2]
+scaledown_silence_duration = "15m"
+scaledown_silence_user = "ops.lambda"
+scaledown_silence_message = "Shutting down."
+"#;
+        let mut expected = FunctionConfig::default();
+        expected.asg.scaledown_silence_user = "ops.lambda".to_string();
+        expected.asg.scaledown_silence_message = "Scaled down by ASG.".to_string();
+        expected.ec2.scaledown_silence_user = "ops.lambda".to_string();
+        expected.ec2.scaledown_silence_message = "Shutting down.".to_string();
+
+        let config: Result<FunctionConfig, _> = toml::from_str(toml);
+
+        asserting("function config loads successfully")
+            .that(&config)
+            .is_ok()
+            .is_equal_to(&expected);
+    }
 }
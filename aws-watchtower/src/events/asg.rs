@@ -1,6 +1,12 @@
-use crate::{asg_mapping::Mapping, config::FunctionConfig, error::AwsWatchtowerError, events::HandleResult, metrics};
+use crate::{
+    asg_mapping::Mapping,
+    config::{Asg, FunctionConfig},
+    error::AwsWatchtowerError,
+    events::HandleResult,
+    metrics,
+};
 use aws::ec2::asg::AsgScalingInfo;
-use bosun::{Bosun, Datum, Silence, Tags};
+use bosun::{Bosun, Silence, Tags};
 use failure::Error;
 use lambda_runtime::Context;
 use log::{debug, info};
@@ -115,11 +121,11 @@ pub fn handle<T: Bosun>(
             .unwrap_or_else(|| "unmapped".to_string()),
     );
     let value = value.to_string();
-    let datum = Datum::now(metrics::ASG_UP_DOWN, &value, &tags);
+    let datum = metrics::METRICS.datum_now(metrics::ASG_UP_DOWN, &value, &tags)?;
     bosun.emit_datum(&datum)?;
 
     if let AsgLifeCycleEvent::SuccessfulTermination(ref details) = event {
-        set_bosun_silence(details, &config.asg.scaledown_silence_duration, mapping, bosun)?
+        set_bosun_silence(details, &config.asg, mapping, bosun)?
     };
 
     let auto_scaling_info = AsgScalingInfo {
@@ -133,7 +139,7 @@ pub fn handle<T: Bosun>(
 
 fn set_bosun_silence(
     details: &TerminationDetails,
-    duration: &str,
+    asg_config: &Asg,
     mapping: Option<&Mapping>,
     bosun: &dyn Bosun,
 ) -> Result<(), Error> {
@@ -144,9 +150,18 @@ fn set_bosun_silence(
     })?;
 
     let host = format!("{}{}*", &host_prefix, details.instance_id);
-    info!("Setting silence of {} for host '{}'.", duration, host);
+    info!(
+        "Setting silence of {} for host '{}'.",
+        asg_config.scaledown_silence_duration, host
+    );
 
-    let silence = Silence::host(&host, duration);
+    let silence = Silence::builder()
+        .host(host)
+        .duration(asg_config.scaledown_silence_duration.as_str())
+        .forget(true)
+        .user(asg_config.scaledown_silence_user.as_str())
+        .message(asg_config.scaledown_silence_message.as_str())
+        .build()?;
     bosun.set_silence(&silence)?;
 
     Ok(())
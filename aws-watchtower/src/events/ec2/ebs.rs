@@ -1,7 +1,7 @@
 use crate::{config::FunctionConfig, events::HandleResult, metrics};
 
 use aws::{self, AwsClientConfig, AwsError};
-use bosun::{Bosun, Datum, Tags};
+use bosun::{Bosun, Tags};
 use failure::Error;
 use lambda_runtime::Context;
 use log::{debug, info};
@@ -124,7 +124,7 @@ pub fn handle<T: Bosun>(
     tags.insert("event".to_string(), event.detail.event.to_string());
     tags.insert("result".to_string(), event.detail.result.to_string());
     let value = change_value.to_string();
-    let datum = Datum::now(metrics::EBS_VOLUME_EVENT, &value, &tags);
+    let datum = metrics::METRICS.datum_now(metrics::EBS_VOLUME_EVENT, &value, &tags)?;
     bosun.emit_datum(&datum)?;
 
     let res = if event.detail.event == VolumeEventType::CreateVolume {
@@ -148,7 +148,7 @@ pub fn handle<T: Bosun>(
         let mut tags = Tags::new();
         tags.insert("encrypted".to_string(), volume_info.encrypted.to_string());
         let value = value.to_string();
-        let datum = Datum::now(metrics::EBS_VOLUME_CREATION_RESULT, &value, &tags);
+        let datum = metrics::METRICS.datum_now(metrics::EBS_VOLUME_CREATION_RESULT, &value, &tags)?;
         bosun.emit_datum(&datum)?;
 
         HandleResult::VolumeInfo { volume_info }
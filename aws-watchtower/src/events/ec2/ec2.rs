@@ -1,9 +1,14 @@
-use crate::{asg_mapping::Mapping, config::FunctionConfig, events::HandleResult, metrics};
+use crate::{
+    asg_mapping::Mapping,
+    config::{Ec2, FunctionConfig},
+    events::HandleResult,
+    metrics,
+};
 use aws::{
     ec2::ec2::{Ec2State, Ec2StateInfo},
     AwsClientConfig,
 };
-use bosun::{Bosun, Datum, Silence, Tags};
+use bosun::{Bosun, Silence, Tags};
 use failure::Error;
 use lambda_runtime::Context;
 use log::{debug, info};
@@ -108,10 +113,11 @@ pub fn handle<T: Bosun>(
             .unwrap_or_else(|| "unmapped".to_string()),
     );
     let value = (state_change.detail.state as u32).to_string();
-    let datum = Datum::now(metrics::EC2_STATE_CHANGE, &value, &tags);
+    let datum = metrics::METRICS.datum_now(metrics::EC2_STATE_CHANGE, &value, &tags)?;
     bosun.emit_datum(&datum)?;
 
     let instance_going_down = state_change.detail.state.is_going_down();
+    let instance_stopped = state_change.detail.state == Ec2State::Stopped;
     let instance_already_silened = {
         let silenced_instances = SILENCED_INSTANCES
             .read()
@@ -124,13 +130,19 @@ pub fn handle<T: Bosun>(
     // auto-scaling lifecycle event. Therefore we're not going to set a silence to
     // prevent silencing a infrastructure problem.
     match mapping {
+        // A stopped instance has not been terminated, so the short silence set while it was going down would
+        // only hide real problems. In this case, we clear it again. The silence is looked up in Bosun, because it may
+        // have been set by another or an earlier instance of this function.
+        Some(mapping) if instance_stopped => {
+            clear_bosun_silence(&state_change.detail.instance_id, &config.ec2, mapping, bosun)?;
+            // Save state: this instance is not silenced anymore
+            let mut silenced_instances = SILENCED_INSTANCES
+                .write()
+                .expect("Could not retrieve Mutex lock (w) for SILENCED_INSTANCES");
+            silenced_instances.remove(&state_change.detail.instance_id);
+        }
         Some(ref mapping) if instance_going_down && !instance_already_silened => {
-            set_bosun_silence(
-                &state_change.detail.instance_id,
-                &config.ec2.scaledown_silence_duration,
-                mapping,
-                bosun,
-            )?;
+            set_bosun_silence(&state_change.detail.instance_id, &config.ec2, mapping, bosun)?;
             // Save state: this instance has been silenced
             let mut silenced_instances = SILENCED_INSTANCES
                 .write()
@@ -165,20 +177,60 @@ pub fn handle<T: Bosun>(
     Ok(HandleResult::Ec2StateInfo { ec2_state_info })
 }
 
-fn set_bosun_silence(instance_id: &str, duration: &str, mapping: &Mapping, bosun: &dyn Bosun) -> Result<(), Error> {
+fn set_bosun_silence(instance_id: &str, ec2_config: &Ec2, mapping: &Mapping, bosun: &dyn Bosun) -> Result<(), Error> {
     let host = format!("{}{}*", &mapping.host_prefix, instance_id);
-    info!("Setting silence of {} for host '{}'.", duration, host);
+    info!(
+        "Setting silence of {} for host '{}'.",
+        ec2_config.scaledown_silence_duration, host
+    );
 
-    let silence = Silence::host(&host, duration);
+    let silence = Silence::builder()
+        .host(host)
+        .duration(ec2_config.scaledown_silence_duration.as_str())
+        .forget(true)
+        .user(ec2_config.scaledown_silence_user.as_str())
+        .message(ec2_config.scaledown_silence_message.as_str())
+        .build()?;
     bosun.set_silence(&silence)?;
 
     Ok(())
 }
 
+/// Clears the silences `set_bosun_silence` has set for `instance_id`; other silences for the same host, e.g., set
+/// for an ASG termination, are kept.
+fn clear_bosun_silence(instance_id: &str, ec2_config: &Ec2, mapping: &Mapping, bosun: &dyn Bosun) -> Result<(), Error> {
+    let host = format!("{}{}*", &mapping.host_prefix, instance_id);
+    let tag_string = format!("host={}", host);
+
+    let silences = bosun.list_silences()?;
+    let ids: Vec<_> = silences
+        .iter()
+        .filter(|(_, silence)| {
+            silence.tag_string == tag_string
+                && silence.user == ec2_config.scaledown_silence_user
+                && silence.message == ec2_config.scaledown_silence_message
+        })
+        .map(|(id, _)| id)
+        .collect();
+    if ids.is_empty() {
+        debug!("No silence to clear for host '{}'.", host);
+        return Ok(());
+    }
+
+    info!("Clearing {} silences for host '{}'.", ids.len(), host);
+    for id in ids {
+        bosun.clear_silence(id)?;
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use bosun::{testing::BosunMockClient, SilenceInfo, Silences, Tags};
+    use chrono::{Duration, Utc};
     use serde_json::json;
     use spectral::prelude::*;
 
@@ -243,4 +295,60 @@ mod tests {
 
         assert_that(&event).is_ok();
     }
+
+    fn ec2_config() -> Ec2 {
+        Ec2 {
+            scaledown_silence_duration: "5m".to_string(),
+            scaledown_silence_user: "kevin.lambda".to_string(),
+            scaledown_silence_message: "Host is shutting down and may be terminated by ASG.".to_string(),
+        }
+    }
+
+    fn silence_for_host(host: &str, message: &str) -> SilenceInfo {
+        let mut tags = Tags::new();
+        tags.insert("host".to_string(), host.to_string());
+
+        SilenceInfo {
+            start: Utc::now(),
+            end: Utc::now() + Duration::minutes(15),
+            alert: "".to_string(),
+            tags: Some(tags),
+            tag_string: format!("host={}", host),
+            forget: true,
+            user: "kevin.lambda".to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn clear_bosun_silence_for_stopped_instance() {
+        setup();
+
+        let shutting_down = "Host is shutting down and may be terminated by ASG.";
+        let terminated = "Host has been terminated by ASG.";
+        let mut silences = Silences::new();
+        silences.insert(
+            "1".to_string(),
+            silence_for_host("webserver-i-abcd1111*", shutting_down),
+        );
+        silences.insert(
+            "2".to_string(),
+            silence_for_host("webserver-i-abcd2222*", shutting_down),
+        );
+        silences.insert("3".to_string(), silence_for_host("webserver-i-abcd1111*", terminated));
+        let bosun = BosunMockClient::with_silences(silences);
+        let mapping = Mapping {
+            search: "webserver".to_string(),
+            tag_name: "webserver".to_string(),
+            host_prefix: "webserver-".to_string(),
+        };
+
+        let res = clear_bosun_silence("i-abcd1111", &ec2_config(), &mapping, &bosun);
+
+        assert_that(&res).is_ok();
+        let silences = bosun.list_silences().unwrap();
+        assert_that(&silences).has_length(2);
+        assert_that(&silences.contains_key("2")).is_true();
+        assert_that(&silences.contains_key("3")).is_true();
+    }
 }
@@ -3,10 +3,10 @@ use aws::{
     ec2::{asg::AsgScalingInfo, ebs::VolumeInfo, ec2::Ec2StateInfo},
     AwsClientConfig,
 };
-use bosun::{Bosun, Datum, Tags};
+use bosun::{Bosun, Tags};
 use failure::{Error, Fail};
 use lambda_runtime::Context;
-use log::debug;
+use log::{debug, warn};
 use serde_derive::{Deserialize, Serialize};
 use serde_json::{self, Value};
 
@@ -49,20 +49,21 @@ pub fn handle<T: Bosun>(
     bosun: &T,
 ) -> Result<HandleResult, Error> {
     let tags = Tags::new();
-    let datum = Datum::now(lambda::metrics::LAMBDA_INVOCATION_COUNT, "1", &tags);
-    bosun.emit_datum(&datum)?;
+    let datum = lambda::metrics::METRICS.datum_now(lambda::metrics::LAMBDA_INVOCATION_COUNT, "1", &tags);
+    // A monitoring outage must not keep us from handling the event.
+    if let Err(err) = datum.and_then(|datum| bosun.emit_datum(&datum)) {
+        warn!("Failed to emit invocation count because {}.", err);
+    }
 
     let res = parse_event(json).and_then(|event| handle_event(aws_client_config, event, ctx, &config, bosun));
 
-    match res {
-        Ok(_) => {
-            let datum = Datum::now(lambda::metrics::LAMBDA_INVOCATION_RESULT, "0", &tags);
-            bosun.emit_datum(&datum)?
-        }
-        Err(_) => {
-            let datum = Datum::now(lambda::metrics::LAMBDA_INVOCATION_RESULT, "1", &tags);
-            bosun.emit_datum(&datum)?
-        }
+    let value = match res {
+        Ok(_) => "0",
+        Err(_) => "1",
+    };
+    let datum = lambda::metrics::METRICS.datum_now(lambda::metrics::LAMBDA_INVOCATION_RESULT, value, &tags);
+    if let Err(err) = datum.and_then(|datum| bosun.emit_datum(&datum)) {
+        warn!("Failed to emit invocation result because {}.", err);
     }
 
     res
@@ -95,7 +96,11 @@ mod tests {
     use super::*;
 
     use crate::asg_mapping::{Mapping, Mappings};
-    use bosun::testing::{BosunCallStats, BosunMockClient};
+    use crate::metrics;
+    use bosun::{
+        testing::{BosunCallStats, BosunMockClient, Call},
+        BosunError,
+    };
 
     use serde_json::json;
     use spectral::prelude::*;
@@ -193,5 +198,62 @@ mod tests {
             .that(&bosun_stats)
             .named("actual calls")
             .is_equal_to(&expected);
+
+        let scaling = bosun.datum_with_tag(metrics::ASG_UP_DOWN, "asg", "my");
+        asserting("scaling datum")
+            .that(&scaling)
+            .is_some()
+            .map(|x| &x.value)
+            .is_equal_to("-1".to_string());
+
+        let silence = bosun.silence_with_tag("host", "my-server-i-1234567890abcdef0*");
+        asserting("silence for terminated host").that(&silence).is_some();
+        let silence = silence.unwrap();
+        assert_that(&silence.duration()).is_equal_to(Some("24h"));
+        assert_that(&silence.user()).is_equal_to(config.asg.scaledown_silence_user.as_str());
+        assert_that(&silence.forget()).is_true();
+    }
+
+    #[test]
+    fn test_handle_asg_successful_termination_with_bosun_failing_silence() {
+        setup();
+
+        let aws_client_config = AwsClientConfig::new().expect("Failed to create AWS client config.");
+        let bosun = BosunMockClient::default().fail_always(
+            Call::SetSilence,
+            BosunError::ReceiveError("500 Internal Server Error".to_string()),
+        );
+        let ctx = Context::default();
+        let mut config = FunctionConfig::default();
+        config.asg.mappings = Mappings {
+            items: vec![Mapping {
+                search: "my".to_string(),
+                tag_name: "my".to_string(),
+                host_prefix: "my-server-".to_string(),
+            }],
+        };
+        let event = json!({
+          "version": "0",
+          "id": "12345678-1234-1234-1234-123456789012",
+          "detail-type": "EC2 Instance Terminate Successful",
+          "source": "aws.autoscaling",
+          "account": "123456789012",
+          "time": "yyyy-mm-ddThh:mm:ssZ",
+          "region": "us-west-2",
+          "resources": [],
+          "detail": {
+              "AutoScalingGroupName": "my-auto-scaling-group",
+              "RequestId": "12345678-1234-1234-1234-123456789012",
+              "EC2InstanceId": "i-1234567890abcdef0"
+          }
+        });
+
+        let res = handle(&aws_client_config, event, &ctx, &config, &bosun);
+        assert_that!(&res).is_err();
+
+        assert_that(&bosun.calls(Call::SetSilence)).is_equal_to(1);
+        let result = bosun.data_for(lambda::metrics::LAMBDA_INVOCATION_RESULT);
+        assert_that(&result).has_length(1);
+        assert_that(&result[0].value.as_str()).is_equal_to("1");
     }
 }
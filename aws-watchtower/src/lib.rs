@@ -1,13 +1,12 @@
 use crate::config::{EncryptedFunctionConfig, FunctionConfig};
 use aws::AwsClientConfig;
 use failure::Error;
-use lambda::{self, config::EncryptedConfig, FunctionVersion};
+use lambda::{self, asg_mapping, config::EncryptedConfig, FunctionVersion};
 use lambda_runtime::{error::HandlerError, Context};
 use log::{debug, info};
 use serde_json::Value;
 use std::sync::atomic::{AtomicUsize, Ordering};
 
-mod asg_mapping;
 pub mod config;
 pub mod error;
 mod events;
@@ -48,7 +47,8 @@ fn run(json: Value, ctx: &Context) -> Result<(), Error> {
     }
 
     // Run per each invocation
-    let bosun = lambda::bosun::init(&CONFIG.bosun, ctx).map_err(|e| ctx.new_error(e.to_string().as_str()))?;
+    let bosun = lambda::bosun::init(&CONFIG.bosun, &AWS_CLIENT_CONFIG, ctx)
+        .map_err(|e| ctx.new_error(e.to_string().as_str()))?;
 
     // Only run once per instance of lambda function
     if invocation_counter == 0 {
@@ -4,7 +4,8 @@ use chrono::{prelude::*, Duration};
 use failure::Error;
 use log::{debug, trace};
 use rusoto_cloudwatch::{
-    CloudWatch, CloudWatchClient, Dimension, GetMetricDataInput, Metric as RusotoMetric, MetricDataQuery, MetricStat,
+    CloudWatch, CloudWatchClient, Dimension, GetMetricDataInput, Metric as RusotoMetric, MetricDataQuery, MetricDatum,
+    MetricStat, PutMetricDataInput,
 };
 use serde_derive::Serialize;
 
@@ -138,3 +139,65 @@ pub fn get_burst_balances<T: Into<Option<Duration>>>(
 
     Ok(metric_data_results)
 }
+
+/// CloudWatch accepts at most 20 metric data per `PutMetricData` request.
+const MAX_METRIC_DATA_PER_REQUEST: usize = 20;
+
+/// CloudWatch accepts at most 10 dimensions per metric datum.
+pub const MAX_DIMENSIONS_PER_METRIC: usize = 10;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricValue {
+    pub name: String,
+    pub timestamp: DateTime<Utc>,
+    pub value: f64,
+    pub dimensions: Vec<(String, String)>,
+}
+
+impl From<&MetricValue> for MetricDatum {
+    fn from(x: &MetricValue) -> Self {
+        let dimensions = x
+            .dimensions
+            .iter()
+            .take(MAX_DIMENSIONS_PER_METRIC)
+            .map(|(name, value)| Dimension {
+                name: name.clone(),
+                value: value.clone(),
+            })
+            .collect();
+        MetricDatum {
+            metric_name: x.name.clone(),
+            dimensions: Some(dimensions),
+            timestamp: Some(x.timestamp.trunc_subsecs(3).to_rfc3339()),
+            value: Some(x.value),
+            ..Default::default()
+        }
+    }
+}
+
+pub fn put_metric_data(
+    aws_client_config: &AwsClientConfig,
+    namespace: &str,
+    values: &[MetricValue],
+) -> Result<(), Error> {
+    let credentials_provider = aws_client_config.credentials_provider.clone();
+    let http_client = aws_client_config.http_client.clone();
+    let cloudwatch = CloudWatchClient::new_with(http_client, credentials_provider, aws_client_config.region.clone());
+
+    for chunk in values.chunks(MAX_METRIC_DATA_PER_REQUEST) {
+        let request = PutMetricDataInput {
+            metric_data: chunk.iter().map(Into::into).collect(),
+            namespace: namespace.to_string(),
+        };
+        trace!("CloudWatch put metric data request: '{:?}'", request);
+
+        cloudwatch.put_metric_data(request).sync()?;
+        debug!(
+            "Put {} metric data to CloudWatch namespace '{}'.",
+            chunk.len(),
+            namespace
+        );
+    }
+
+    Ok(())
+}
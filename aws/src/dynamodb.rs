@@ -0,0 +1,161 @@
+use crate::AwsClientConfig;
+use failure::{err_msg, Error};
+use log::{debug, error};
+use rusoto_dynamodb::{
+    BatchWriteItemError, BatchWriteItemInput, DynamoDb, DynamoDbClient, PutRequest, QueryError, QueryInput,
+    WriteRequest,
+};
+use std::collections::HashMap;
+
+pub use rusoto_dynamodb::AttributeValue;
+
+/// Maximum number of items DynamoDB accepts per `BatchWriteItem` request.
+pub const MAX_BATCH_WRITE_ITEMS: usize = 25;
+/// Number of times unprocessed items of a batch are resubmitted before giving up.
+const UNPROCESSED_ITEMS_RETRIES: usize = 5;
+
+pub type Item = HashMap<String, AttributeValue>;
+
+/// Puts `items` into `table` in batches of `MAX_BATCH_WRITE_ITEMS`, replacing any items with the same keys.
+pub fn batch_put_items(aws_client_config: &AwsClientConfig, table: &str, items: Vec<Item>) -> Result<(), Error> {
+    debug!("Put {} items to table '{}'", items.len(), table);
+
+    let credentials_provider = aws_client_config.credentials_provider.clone();
+    let http_client = aws_client_config.http_client.clone();
+    let dynamodb = DynamoDbClient::new_with(http_client, credentials_provider, aws_client_config.region.clone());
+
+    let mut requests: Vec<WriteRequest> = items
+        .into_iter()
+        .map(|item| WriteRequest {
+            delete_request: None,
+            put_request: Some(PutRequest { item }),
+        })
+        .collect();
+    while !requests.is_empty() {
+        let rest = requests.split_off(requests.len().min(MAX_BATCH_WRITE_ITEMS));
+        let mut batch = requests;
+        requests = rest;
+
+        let mut retries = 0;
+        while !batch.is_empty() {
+            if retries > UNPROCESSED_ITEMS_RETRIES {
+                return Err(err_msg(format!(
+                    "{} items have not been processed after {} retries",
+                    batch.len(),
+                    UNPROCESSED_ITEMS_RETRIES
+                )));
+            }
+            let mut request_items = HashMap::new();
+            request_items.insert(table.to_string(), batch);
+            let request = BatchWriteItemInput {
+                request_items,
+                ..Default::default()
+            };
+            let res = dynamodb.batch_write_item(request).sync();
+            debug!("Finished batch write item request; success={}.", res.is_ok());
+            if let Err(BatchWriteItemError::Unknown(ref buf)) = res {
+                let str = String::from_utf8_lossy(&buf.body);
+                error!("Error: {}", str);
+            }
+
+            batch = res?
+                .unprocessed_items
+                .and_then(|mut x| x.remove(table))
+                .unwrap_or_default();
+            retries += 1;
+        }
+    }
+
+    Ok(())
+}
+
+/// Selects the items of a table or index by their key
+#[derive(Debug)]
+pub struct KeyQuery<'a> {
+    pub table: &'a str,
+    /// Queries this global secondary index instead of the table
+    pub index: Option<&'a str>,
+    /// Condition on the partition key and optionally the sort key, e.g., `run_id = :run_id`
+    pub key_condition: String,
+    /// Values of the placeholders in `key_condition`, e.g., `:run_id`
+    pub values: Item,
+}
+
+/// Reads all items matching `query`, following `LastEvaluatedKey` until the last page.
+pub fn query(aws_client_config: &AwsClientConfig, query: KeyQuery) -> Result<Vec<Item>, Error> {
+    debug!("Query table '{}' for '{}'", query.table, query.key_condition);
+
+    let credentials_provider = aws_client_config.credentials_provider.clone();
+    let http_client = aws_client_config.http_client.clone();
+    let dynamodb = DynamoDbClient::new_with(http_client, credentials_provider, aws_client_config.region.clone());
+
+    let mut items = Vec::new();
+    let mut exclusive_start_key = None;
+    loop {
+        let request = QueryInput {
+            table_name: query.table.to_string(),
+            index_name: query.index.map(str::to_string),
+            key_condition_expression: Some(query.key_condition.clone()),
+            expression_attribute_values: Some(query.values.clone()),
+            exclusive_start_key: exclusive_start_key.take(),
+            ..Default::default()
+        };
+        let res = dynamodb.query(request).sync();
+        debug!("Finished query request; success={}.", res.is_ok());
+        if let Err(QueryError::Unknown(ref buf)) = res {
+            let str = String::from_utf8_lossy(&buf.body);
+            error!("Error: {}", str);
+        }
+
+        let res = res?;
+        items.extend(res.items.unwrap_or_default());
+        match res.last_evaluated_key {
+            Some(key) => {
+                debug!("Queried {} items so far; continuing.", items.len());
+                exclusive_start_key = Some(key);
+            }
+            None => return Ok(items),
+        }
+    }
+}
+
+/// Creates a string attribute value.
+pub fn string_value<S: Into<String>>(value: S) -> AttributeValue {
+    AttributeValue {
+        s: Some(value.into()),
+        ..Default::default()
+    }
+}
+
+/// Creates a number attribute value.
+pub fn number_value<T: ToString>(value: T) -> AttributeValue {
+    AttributeValue {
+        n: Some(value.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Returns the string attribute `name` of `item`.
+pub fn string_attribute<'a>(item: &'a Item, name: &str) -> Option<&'a str> {
+    item.get(name).and_then(|x| x.s.as_ref()).map(String::as_str)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use spectral::prelude::*;
+
+    #[test]
+    fn read_string_attribute() {
+        testing::setup();
+
+        let mut item = Item::new();
+        item.insert("run_id".to_string(), string_value("42"));
+        item.insert("seq".to_string(), number_value(1));
+
+        assert_that(&string_attribute(&item, "run_id")).is_equal_to(Some("42"));
+        assert_that(&string_attribute(&item, "seq")).is_none();
+        assert_that(&string_attribute(&item, "record")).is_none();
+    }
+}
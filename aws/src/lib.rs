@@ -4,9 +4,14 @@ use std::sync::Arc;
 
 pub mod auth;
 pub mod cloudwatch;
+pub mod dynamodb;
 pub mod ec2;
 pub mod iam;
 pub mod kms;
+pub mod paging;
+pub mod s3;
+pub mod ses;
+pub mod sns;
 
 #[derive(Debug, Fail)]
 pub enum AwsError {
@@ -16,6 +21,7 @@ pub enum AwsError {
 
 pub type CredentialsProvider = AutoRefreshingProvider<auth::CeresAwsCredentialProvider>;
 
+#[derive(Clone)]
 pub struct AwsClientConfig {
     credentials_provider: Arc<CredentialsProvider>,
     http_client: Arc<HttpClient>,
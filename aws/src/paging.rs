@@ -0,0 +1,112 @@
+//! Marker-based paging of AWS list calls, e.g., IAM's `ListUsers` and `ListAccessKeys`.
+//!
+//! AWS returns at most `max_items` results per call and sets `is_truncated` and `marker` if there are more. Passing
+//! the marker to the next call continues the listing.
+
+use failure::{err_msg, Error};
+use log::debug;
+
+/// Maximum number of items IAM returns per page.
+pub const MAX_ITEMS: i64 = 1000;
+
+/// A single page of a marker-based listing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Marker to request the next page with; `None` on the last page
+    pub next_marker: Option<String>,
+}
+
+impl<T> Page<T> {
+    /// Creates a page from the items, `is_truncated`, and `marker` of an AWS response.
+    pub fn new(items: Vec<T>, is_truncated: Option<bool>, marker: Option<String>) -> Result<Page<T>, Error> {
+        let next_marker = match (is_truncated.unwrap_or(false), marker) {
+            (false, _) => None,
+            (true, Some(marker)) => Some(marker),
+            (true, None) => return Err(err_msg("truncated result without marker")),
+        };
+
+        Ok(Page { items, next_marker })
+    }
+}
+
+/// Calls `fetch` with the marker of the previous page, starting with `None`, until the last page and concatenates
+/// the items of all pages.
+pub fn collect_pages<T, F>(mut fetch: F) -> Result<Vec<T>, Error>
+where
+    F: FnMut(Option<String>) -> Result<Page<T>, Error>,
+{
+    let mut items = Vec::new();
+    let mut marker = None;
+    loop {
+        let page = fetch(marker.take())?;
+        items.extend(page.items);
+        match page.next_marker {
+            Some(next) => {
+                debug!("Fetched {} items so far; continuing at marker '{}'.", items.len(), next);
+                marker = Some(next);
+            }
+            None => return Ok(items),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use spectral::prelude::*;
+
+    /// Pretends to list `total` items in pages of `size` items using the index of the next item as marker.
+    fn fetch(total: usize, size: usize, marker: Option<String>) -> Result<Page<usize>, Error> {
+        let start = marker.map(|x| x.parse::<usize>().unwrap()).unwrap_or(0);
+        let end = (start + size).min(total);
+        let is_truncated = end < total;
+
+        Page::new((start..end).collect(), Some(is_truncated), Some(end.to_string()))
+    }
+
+    #[test]
+    fn collect_all_pages() {
+        testing::setup();
+
+        let res = collect_pages(|marker| fetch(250, 100, marker));
+
+        assert_that(&res).is_ok().is_equal_to((0..250).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn collect_single_page() {
+        testing::setup();
+
+        let mut calls = 0;
+        let res = collect_pages(|marker| {
+            calls += 1;
+            fetch(10, 100, marker)
+        });
+
+        assert_that(&res).is_ok().has_length(10);
+        assert_that(&calls).is_equal_to(1);
+    }
+
+    #[test]
+    fn collect_pages_fails_on_failing_page() {
+        testing::setup();
+
+        let res = collect_pages(|marker| match marker {
+            None => fetch(250, 100, None),
+            Some(_) => Err(err_msg("throttled")),
+        });
+
+        assert_that(&res).is_err();
+    }
+
+    #[test]
+    fn truncated_page_without_marker_fails() {
+        testing::setup();
+
+        let res = Page::new(vec![1, 2, 3], Some(true), None);
+
+        assert_that(&res).is_err();
+    }
+}
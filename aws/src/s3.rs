@@ -0,0 +1,90 @@
+use crate::paging::{collect_pages, Page};
+use crate::AwsClientConfig;
+use failure::Error;
+use log::{debug, error};
+use rusoto_s3::{GetObjectError, GetObjectRequest, ListObjectsV2Request, PutObjectRequest, S3Client, S3};
+use std::io::Read;
+
+/// Reads object `key` from `bucket`; returns `None`, if the object does not exist.
+pub fn get_object(aws_client_config: &AwsClientConfig, bucket: &str, key: &str) -> Result<Option<Vec<u8>>, Error> {
+    debug!("Get object '{}' from bucket '{}'", key, bucket);
+
+    let credentials_provider = aws_client_config.credentials_provider.clone();
+    let http_client = aws_client_config.http_client.clone();
+    let s3 = S3Client::new_with(http_client, credentials_provider, aws_client_config.region.clone());
+
+    let request = GetObjectRequest {
+        bucket: bucket.to_string(),
+        key: key.to_string(),
+        ..Default::default()
+    };
+    let res = s3.get_object(request).sync();
+    debug!("Finished get object request; success={}.", res.is_ok());
+    match res {
+        Err(GetObjectError::NoSuchKey(_)) => return Ok(None),
+        // rusoto reports a missing key as unknown error, if it cannot parse the error body
+        Err(GetObjectError::Unknown(ref buf)) if buf.status.as_u16() == 404 => return Ok(None),
+        Err(GetObjectError::Unknown(ref buf)) => {
+            let str = String::from_utf8_lossy(&buf.body);
+            error!("Error: {}", str);
+        }
+        _ => {}
+    }
+
+    let mut buf = Vec::new();
+    if let Some(body) = res?.body {
+        body.into_blocking_read().read_to_end(&mut buf)?;
+    }
+
+    Ok(Some(buf))
+}
+
+/// Writes `body` to object `key` in `bucket`, replacing any existing object.
+pub fn put_object(aws_client_config: &AwsClientConfig, bucket: &str, key: &str, body: Vec<u8>) -> Result<(), Error> {
+    debug!("Put object '{}' to bucket '{}'", key, bucket);
+
+    let credentials_provider = aws_client_config.credentials_provider.clone();
+    let http_client = aws_client_config.http_client.clone();
+    let s3 = S3Client::new_with(http_client, credentials_provider, aws_client_config.region.clone());
+
+    let request = PutObjectRequest {
+        bucket: bucket.to_string(),
+        key: key.to_string(),
+        body: Some(body.into()),
+        ..Default::default()
+    };
+    let res = s3.put_object(request).sync();
+    debug!("Finished put object request; success={}.", res.is_ok());
+    res?;
+
+    Ok(())
+}
+
+/// Lists the keys of all objects in `bucket` starting with `prefix`, following continuation tokens until the last page.
+pub fn list_objects(aws_client_config: &AwsClientConfig, bucket: &str, prefix: &str) -> Result<Vec<String>, Error> {
+    debug!("List objects with prefix '{}' in bucket '{}'", prefix, bucket);
+
+    let credentials_provider = aws_client_config.credentials_provider.clone();
+    let http_client = aws_client_config.http_client.clone();
+    let s3 = S3Client::new_with(http_client, credentials_provider, aws_client_config.region.clone());
+
+    collect_pages(|continuation_token| {
+        let request = ListObjectsV2Request {
+            bucket: bucket.to_string(),
+            prefix: Some(prefix.to_string()),
+            continuation_token,
+            ..Default::default()
+        };
+        let res = s3.list_objects_v2(request).sync();
+        debug!("Finished list objects request; success={}.", res.is_ok());
+        let res = res?;
+        let keys = res
+            .contents
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|x| x.key)
+            .collect();
+
+        Page::new(keys, res.is_truncated, res.next_continuation_token)
+    })
+}
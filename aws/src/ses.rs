@@ -0,0 +1,51 @@
+use crate::AwsClientConfig;
+use failure::Error;
+use log::{debug, error};
+use rusoto_ses::{Body, Content, Destination, Message, SendEmailError, SendEmailRequest, Ses, SesClient};
+
+/// Sends a plain text email from `source`, which must be a verified SES identity, to all addresses in `to`.
+pub fn send_email(
+    aws_client_config: &AwsClientConfig,
+    source: &str,
+    to: &[String],
+    subject: &str,
+    body: &str,
+) -> Result<(), Error> {
+    debug!("Sending email '{}' to {:?}", subject, to);
+
+    let credentials_provider = aws_client_config.credentials_provider.clone();
+    let http_client = aws_client_config.http_client.clone();
+    let ses = SesClient::new_with(http_client, credentials_provider, aws_client_config.region.clone());
+
+    let request = SendEmailRequest {
+        source: source.to_string(),
+        destination: Destination {
+            to_addresses: Some(to.to_vec()),
+            ..Default::default()
+        },
+        message: Message {
+            subject: utf8_content(subject),
+            body: Body {
+                html: None,
+                text: Some(utf8_content(body)),
+            },
+        },
+        ..Default::default()
+    };
+    let res = ses.send_email(request).sync();
+    debug!("Finished send email request; success={}.", res.is_ok());
+    if let Err(SendEmailError::Unknown(ref buf)) = res {
+        let str = String::from_utf8_lossy(&buf.body);
+        error!("Error: {}", str);
+    }
+    res?;
+
+    Ok(())
+}
+
+fn utf8_content(data: &str) -> Content {
+    Content {
+        charset: Some("UTF-8".to_string()),
+        data: data.to_string(),
+    }
+}
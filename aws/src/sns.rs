@@ -0,0 +1,34 @@
+use crate::AwsClientConfig;
+use failure::Error;
+use log::{debug, error};
+use rusoto_sns::{PublishError, PublishInput, Sns, SnsClient};
+
+/// Publishes `message` to the SNS topic `topic_arn`.
+pub fn publish(
+    aws_client_config: &AwsClientConfig,
+    topic_arn: &str,
+    subject: &str,
+    message: &str,
+) -> Result<(), Error> {
+    debug!("Publishing message to SNS topic '{}'", topic_arn);
+
+    let credentials_provider = aws_client_config.credentials_provider.clone();
+    let http_client = aws_client_config.http_client.clone();
+    let sns = SnsClient::new_with(http_client, credentials_provider, aws_client_config.region.clone());
+
+    let request = PublishInput {
+        topic_arn: Some(topic_arn.to_string()),
+        subject: Some(subject.to_string()),
+        message: message.to_string(),
+        ..Default::default()
+    };
+    let res = sns.publish(request).sync();
+    debug!("Finished publish request; success={}.", res.is_ok());
+    if let Err(PublishError::Unknown(ref buf)) = res {
+        let str = String::from_utf8_lossy(&buf.body);
+        error!("Error: {}", str);
+    }
+    res?;
+
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use bosun::{Bosun, BosunClient, Metadata};
+use bosun::{Bosun, BosunClient, Metadata, Rate, Unit};
 
 use std::env;
 
@@ -23,8 +23,8 @@ fn main() {
 
     let metadata = Metadata::new(
         "aws.ec2.asg.scaling.event",
-        "rate",
-        "Scaling",
+        Rate::Rate,
+        Unit::Scaling,
         "ASG up and down scaling event [-1 = down scaling, +1 = up scaling]",
     );
 
@@ -0,0 +1,39 @@
+use bosun::{Bosun, BosunClient};
+
+use std::env;
+
+fn main() {
+    env_logger::init();
+
+    let bosun_url = env::var_os("BOSUN_URL")
+        .expect("Environment variable 'BOSUN_URL' is not set.")
+        .to_string_lossy()
+        .to_string();
+    let bosun_username = env::var_os("BOSUN_USERNAME")
+        .expect("Environment variable 'BOSUN_USERNAME' is not set.")
+        .to_string_lossy()
+        .to_string();
+    let bosun_password = env::var_os("BOSUN_PASSWORD")
+        .expect("Environment variable 'BOSUN_PASSWORD' is not set.")
+        .to_string_lossy()
+        .to_string();
+    // Optionally, only show silences created by this user, e.g., the user the lambda functions use.
+    let silence_user = env::var_os("SILENCE_USER").map(|x| x.to_string_lossy().to_string());
+
+    let mut bosun = BosunClient::new(&bosun_url, 5);
+    bosun.set_basic_auth(bosun_username, Some(bosun_password));
+
+    let silences = bosun.list_silences().expect("Failed to list silences");
+    let mut silences: Vec<_> = silences
+        .into_iter()
+        .filter(|(_, s)| silence_user.as_ref().map(|u| u == &s.user).unwrap_or(true))
+        .collect();
+    silences.sort_by_key(|(_, s)| s.start);
+
+    for (id, s) in silences {
+        println!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            id, s.start, s.end, s.alert, s.tag_string, s.user, s.message
+        );
+    }
+}
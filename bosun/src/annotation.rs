@@ -0,0 +1,200 @@
+//! Annotations mark events like the termination of an instance on Bosun's and Grafana's graphs.
+//!
+//! cf. https://github.com/bosun-monitor/annotate/blob/master/annotate.go
+
+use chrono::{DateTime, Utc};
+use serde_derive::{Deserialize, Serialize};
+
+/// Represents an annotation as stored by Bosun.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Annotation {
+    /// Set by Bosun on creation
+    #[serde(rename = "Id", default, skip_serializing_if = "String::is_empty")]
+    pub id: String,
+    #[serde(rename = "Message", default)]
+    pub message: String,
+    #[serde(rename = "StartDate")]
+    pub start: DateTime<Utc>,
+    #[serde(rename = "EndDate")]
+    pub end: DateTime<Utc>,
+    #[serde(rename = "CreationUser", default)]
+    pub creation_user: String,
+    #[serde(rename = "Url", default)]
+    pub url: String,
+    #[serde(rename = "Source", default)]
+    pub source: String,
+    #[serde(rename = "Host", default)]
+    pub host: String,
+    #[serde(rename = "Owner", default)]
+    pub owner: String,
+    #[serde(rename = "Category", default)]
+    pub category: String,
+}
+
+impl Annotation {
+    /// Creates a new annotation for an event happening _now_.
+    pub fn new<S: Into<String>, T: Into<String>, U: Into<String>>(source: S, category: T, message: U) -> Annotation {
+        let now = Utc::now();
+        Annotation {
+            id: String::new(),
+            message: message.into(),
+            start: now,
+            end: now,
+            creation_user: String::new(),
+            url: String::new(),
+            source: source.into(),
+            host: String::new(),
+            owner: String::new(),
+            category: category.into(),
+        }
+    }
+
+    pub fn start(mut self, start: DateTime<Utc>) -> Self {
+        self.start = start;
+        self
+    }
+
+    pub fn end(mut self, end: DateTime<Utc>) -> Self {
+        self.end = end;
+        self
+    }
+
+    pub fn host<S: Into<String>>(mut self, host: S) -> Self {
+        self.host = host.into();
+        self
+    }
+
+    pub fn creation_user<S: Into<String>>(mut self, creation_user: S) -> Self {
+        self.creation_user = creation_user.into();
+        self
+    }
+
+    pub fn owner<S: Into<String>>(mut self, owner: S) -> Self {
+        self.owner = owner.into();
+        self
+    }
+
+    pub fn url<S: Into<String>>(mut self, url: S) -> Self {
+        self.url = url.into();
+        self
+    }
+}
+
+/// Filters for listing annotations; only annotations overlapping `start` to `end` are returned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnnotationQuery {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub source: Option<String>,
+    pub host: Option<String>,
+    pub category: Option<String>,
+    pub creation_user: Option<String>,
+}
+
+impl AnnotationQuery {
+    pub fn new(start: DateTime<Utc>, end: DateTime<Utc>) -> AnnotationQuery {
+        AnnotationQuery {
+            start,
+            end,
+            source: None,
+            host: None,
+            category: None,
+            creation_user: None,
+        }
+    }
+
+    pub fn source<S: Into<String>>(mut self, source: S) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    pub fn host<S: Into<String>>(mut self, host: S) -> Self {
+        self.host = Some(host.into());
+        self
+    }
+
+    pub fn category<S: Into<String>>(mut self, category: S) -> Self {
+        self.category = Some(category.into());
+        self
+    }
+
+    pub fn creation_user<S: Into<String>>(mut self, creation_user: S) -> Self {
+        self.creation_user = Some(creation_user.into());
+        self
+    }
+
+    /// Query parameters as expected by Bosun's `/api/annotation/query`.
+    pub fn to_params(&self) -> Vec<(&'static str, String)> {
+        let mut params = vec![
+            ("StartDate", self.start.to_rfc3339()),
+            ("EndDate", self.end.to_rfc3339()),
+        ];
+        if let Some(ref source) = self.source {
+            params.push(("Source", source.clone()));
+        }
+        if let Some(ref host) = self.host {
+            params.push(("Host", host.clone()));
+        }
+        if let Some(ref category) = self.category {
+            params.push(("Category", category.clone()));
+        }
+        if let Some(ref creation_user) = self.creation_user {
+            params.push(("CreationUser", creation_user.clone()));
+        }
+
+        params
+    }
+
+    /// Returns `true` if `annotation` matches this query.
+    pub fn matches(&self, annotation: &Annotation) -> bool {
+        fn matches_field(filter: &Option<String>, value: &str) -> bool {
+            filter.as_ref().map(|x| x == value).unwrap_or(true)
+        }
+
+        annotation.start <= self.end
+            && annotation.end >= self.start
+            && matches_field(&self.source, &annotation.source)
+            && matches_field(&self.host, &annotation.host)
+            && matches_field(&self.category, &annotation.category)
+            && matches_field(&self.creation_user, &annotation.creation_user)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use spectral::prelude::*;
+
+    #[test]
+    fn serialize_new_annotation_without_id() {
+        let start = "2020-10-16T10:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let annotation = Annotation::new("aws-scaletower", "burst_balance", "Terminated instance")
+            .start(start)
+            .end(start)
+            .host("i-1234567890abcdef0");
+
+        let json = serde_json::to_value(&annotation).unwrap();
+
+        assert_that(&json.get("Id")).is_none();
+        assert_that(&json["StartDate"].as_str()).is_equal_to(Some("2020-10-16T10:00:00Z"));
+        assert_that(&json["Host"].as_str()).is_equal_to(Some("i-1234567890abcdef0"));
+    }
+
+    #[test]
+    fn deserialize_annotations() {
+        let json = r#"[{"Id":"4e4c8b69-0d6b-4e2a-9a4a-0a6d1b1c7d5e","Message":"Terminated instance","StartDate":"2020-10-16T10:00:00Z","EndDate":"2020-10-16T10:00:00Z","CreationUser":"","Url":"","Source":"aws-scaletower","Host":"i-1234567890abcdef0","Owner":"","Category":"burst_balance"}]"#;
+
+        let annotations: Result<Vec<Annotation>, _> = serde_json::from_str(json);
+
+        assert_that(&annotations).is_ok().has_length(1);
+        let annotations = annotations.unwrap();
+        let query = AnnotationQuery::new(
+            "2020-10-16T09:00:00Z".parse::<DateTime<Utc>>().unwrap(),
+            "2020-10-16T11:00:00Z".parse::<DateTime<Utc>>().unwrap(),
+        )
+        .category("burst_balance");
+        assert_that(&query.matches(&annotations[0])).is_true();
+        assert_that(&query.host("i-other").matches(&annotations[0])).is_false();
+    }
+}
@@ -0,0 +1,160 @@
+//! Read-only views of what Bosun currently sees, i.e., open incidents and evaluated expressions.
+//!
+//! cf. https://github.com/bosun-monitor/bosun/blob/master/models/incidents.go
+
+use std::collections::HashSet;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::Deserializer;
+use serde_derive::Deserialize;
+
+use crate::Tags;
+
+/// Status of an alert; ordered by severity like in Bosun, i.e., `Unknown` is worse than `Critical`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertStatus {
+    None,
+    Normal,
+    Warning,
+    Critical,
+    Unknown,
+}
+
+impl AlertStatus {
+    pub fn is_abnormal(self) -> bool {
+        self > AlertStatus::Normal
+    }
+}
+
+/// Summary of an incident as returned by `/api/incidents/open`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Incident {
+    #[serde(rename = "Id")]
+    pub id: i64,
+    #[serde(rename = "Subject", default)]
+    pub subject: String,
+    /// Unix timestamp in seconds
+    #[serde(rename = "Start")]
+    pub start: i64,
+    #[serde(rename = "AlertName")]
+    pub alert_name: String,
+    #[serde(rename = "Tags", default, deserialize_with = "null_as_default")]
+    pub tags: Tags,
+    #[serde(rename = "CurrentStatus")]
+    pub current_status: AlertStatus,
+    #[serde(rename = "WorstStatus")]
+    pub worst_status: AlertStatus,
+    #[serde(rename = "Unevaluated", default)]
+    pub unevaluated: bool,
+    #[serde(rename = "NeedAck", default)]
+    pub need_ack: bool,
+    #[serde(rename = "Silenced", default)]
+    pub silenced: bool,
+}
+
+impl Incident {
+    /// Checks if this incident carries all `tags` with exactly the same values.
+    pub fn has_tags(&self, tags: &Tags) -> bool {
+        tags.iter()
+            .all(|(k, v)| self.tags.get(k).map(|x| x == v).unwrap_or(false))
+    }
+
+    pub fn start_time(&self) -> DateTime<Utc> {
+        DateTime::from_utc(NaiveDateTime::from_timestamp(self.start, 0), Utc)
+    }
+}
+
+/// Distinct values of tag `host` of all incidents that are currently abnormal and not silenced.
+pub fn alerting_hosts(incidents: &[Incident]) -> HashSet<&str> {
+    incidents
+        .iter()
+        .filter(|x| x.current_status.is_abnormal() && !x.silenced)
+        .filter_map(|x| x.tags.get("host"))
+        .map(String::as_str)
+        .collect()
+}
+
+/// Result of evaluating an expression via `/api/expr`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ExprResult {
+    /// Type of the result, e.g., `number` or `series`
+    #[serde(rename = "Type")]
+    pub kind: String,
+    #[serde(rename = "Results", default, deserialize_with = "null_as_default")]
+    pub results: Vec<ExprValue>,
+}
+
+/// Value of an expression for one group, i.e., tag set.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ExprValue {
+    /// A number for scalars and numbers, or an object of timestamps to numbers for series
+    #[serde(rename = "Value")]
+    pub value: serde_json::Value,
+    #[serde(rename = "Group", default, deserialize_with = "null_as_default")]
+    pub group: Tags,
+}
+
+impl ExprValue {
+    /// Returns the value, if this is a number or scalar.
+    pub fn as_number(&self) -> Option<f64> {
+        self.value.as_f64()
+    }
+}
+
+// Bosun (Go) encodes empty maps and slices as `null`.
+fn null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Default + serde::Deserialize<'de>,
+{
+    <Option<T> as serde::Deserialize>::deserialize(deserializer).map(Option::unwrap_or_default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use spectral::prelude::*;
+
+    #[test]
+    fn deserialize_open_incidents() {
+        let json = r#"[
+  {"Id":42,"Subject":"warning: cpu high on webserver-1","Start":1602842400,"AlertName":"os.cpu.high",
+   "Tags":{"host":"webserver-1"},"TagsString":"{host=webserver-1}","CurrentStatus":"warning",
+   "WorstStatus":"critical","LastAbnormalStatus":"warning","LastAbnormalTime":1602842700,"Unevaluated":false,
+   "NeedAck":true,"Silenced":false,"Actions":null,"Events":null,"WarnNotificationChains":null,
+   "CritNotificationChains":null},
+  {"Id":43,"Subject":"","Start":1602842400,"AlertName":"os.disk.full","Tags":null,"CurrentStatus":"normal",
+   "WorstStatus":"warning","Silenced":false}
+]"#;
+
+        let incidents: Result<Vec<Incident>, _> = serde_json::from_str(json);
+
+        assert_that(&incidents).is_ok().has_length(2);
+        let incidents = incidents.unwrap();
+        assert_that(&incidents[0].worst_status).is_equal_to(AlertStatus::Critical);
+        assert_that(&incidents[1].tags.is_empty()).is_true();
+        assert_that(&alerting_hosts(&incidents).len()).is_equal_to(1);
+    }
+
+    #[test]
+    fn alert_status_is_ordered_by_severity() {
+        assert_that(&AlertStatus::Unknown).is_greater_than(AlertStatus::Critical);
+        assert_that(&AlertStatus::Critical).is_greater_than(AlertStatus::Warning);
+        assert_that(&AlertStatus::Normal.is_abnormal()).is_false();
+    }
+
+    #[test]
+    fn deserialize_expr_result() {
+        let json = r#"{"Type":"number","Results":[{"Computations":[],"Value":3.5,"Group":{"host":"webserver-1"}},
+{"Computations":[],"Value":1,"Group":null}],"Queries":{},"Warnings":null}"#;
+
+        let result: Result<ExprResult, _> = serde_json::from_str(json);
+
+        assert_that(&result).is_ok().map(|x| &x.results).has_length(2);
+        let result = result.unwrap();
+        assert_that(&result.results[0].as_number()).is_equal_to(Some(3.5));
+        assert_that(&result.results[1].group.is_empty()).is_true();
+    }
+}
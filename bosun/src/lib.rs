@@ -1,15 +1,27 @@
-use chrono::Timelike;
+use chrono::{DateTime, Timelike, Utc};
 use failure::Fail;
-use log::{debug, info};
+use log::{debug, error, info, warn};
 use reqwest::StatusCode;
-use serde_derive::Serialize;
-use std::{collections::HashMap, time::Duration};
+use serde::Serializer;
+use serde_derive::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+pub mod annotation;
+pub mod incident;
+pub mod registry;
+pub mod sink;
+pub mod spool;
+
+pub use annotation::{Annotation, AnnotationQuery};
+pub use incident::{AlertStatus, ExprResult, Incident};
+
+use spool::{Spool, SpooledRequest};
 
 /// Result of an attempt to send meta data or a metric datum
 pub type BosunResult = Result<(), BosunError>;
 
 /// Errors which may occur while sending either meta data or metric data.
-#[derive(Debug, Fail)]
+#[derive(Debug, Clone, PartialEq, Eq, Fail)]
 pub enum BosunError {
     /// Failed to create JSON.
     #[fail(display = "failed to parse JSON")]
@@ -20,27 +32,192 @@ pub enum BosunError {
     /// Failed to read from Bosun
     #[fail(display = "failed to process Bosun response because '{}'", _0)]
     ReceiveError(String),
+    /// Failed to build a silence
+    #[fail(display = "invalid silence because {}", _0)]
+    InvalidSilence(&'static str),
+    /// Bosun is temporarily unavailable, i.e., it answered with 5xx even after retrying
+    #[fail(display = "Bosun is unavailable because '{}'", _0)]
+    Unavailable(String),
+    /// Failed to read or write the spool
+    #[fail(display = "failed to access spool because {}", _0)]
+    SpoolError(String),
+    /// Metric has not been registered
+    #[fail(display = "metric '{}' is not registered", _0)]
+    UnknownMetric(String),
+    /// Connection options are invalid, e.g., a CA certificate cannot be parsed
+    #[fail(display = "invalid connection configuration because {}", _0)]
+    InvalidConnection(String),
+    /// Annotation cannot be sent to Bosun
+    #[fail(display = "invalid annotation because {}", _0)]
+    InvalidAnnotation(&'static str),
+}
+
+impl BosunError {
+    /// Returns `true` if the same request may succeed later, e.g., after Bosun is available again.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            BosunError::EmitError(_) | BosunError::Unavailable(_) => true,
+            _ => false,
+        }
+    }
 }
 
 /// Metric tags equivalent to Rust's `HashMap<String, String>`
 pub type Tags = HashMap<String, String>;
 
+/// Default maximum number of data points sent to Bosun in a single request.
+pub const DEFAULT_MAX_BATCH_SIZE: usize = 500;
+
+/// Controls how often and how fast idempotent requests to Bosun are retried after 5xx responses and timeouts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Number of retries after the first attempt; 0 disables retrying
+    pub max_retries: u32,
+    /// Backoff before the first retry; the backoff doubles with every further retry
+    pub initial_backoff: Duration,
+    /// Upper bound for the backoff between two retries
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Never retries.
+    pub fn none() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 0,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the backoff before retry number `retry`, starting at 0.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry);
+        self.initial_backoff
+            .checked_mul(factor)
+            .map(|x| x.min(self.max_backoff))
+            .unwrap_or(self.max_backoff)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(2),
+        }
+    }
+}
+
+/// Transport options for the connection to Bosun, e.g., for reaching Bosun behind an internal CA via a proxy.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConnectionConfig {
+    /// Timeout for establishing a connection; the request timeout still applies to the whole request
+    pub connect_timeout: Option<Duration>,
+    /// Proxy URL for all requests, e.g., `http://proxy.internal:3128`
+    pub proxy: Option<String>,
+    /// PEM encoded certificates trusted in addition to the system's CAs
+    pub ca_certificates: Vec<Vec<u8>>,
+    /// Client certificate and key for mutual TLS
+    pub client_identity: Option<ClientIdentity>,
+}
+
+impl ConnectionConfig {
+    /// Adds all certificates of a PEM encoded CA bundle.
+    pub fn add_ca_bundle(mut self, pem: &[u8]) -> Self {
+        const END: &str = "-----END CERTIFICATE-----";
+        let pem = String::from_utf8_lossy(pem);
+        // Every part but the last one is followed by an end marker, i.e., is a complete certificate.
+        let mut parts: Vec<&str> = pem.split(END).collect();
+        parts.pop();
+        for part in parts {
+            let cert = format!("{}{}", part.trim_start(), END);
+            self.ca_certificates.push(cert.into_bytes());
+        }
+        self
+    }
+
+    /// Returns `true` if TLS options are set, so hosts without scheme are contacted via https.
+    pub fn uses_tls(&self) -> bool {
+        !self.ca_certificates.is_empty() || self.client_identity.is_some()
+    }
+}
+
+/// PKCS#12 encoded client certificate and private key.
+#[derive(Clone, PartialEq, Eq)]
+pub struct ClientIdentity {
+    pub pkcs12: Vec<u8>,
+    pub password: String,
+}
+
+impl std::fmt::Debug for ClientIdentity {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("ClientIdentity")
+            .field("pkcs12", &format!("<{} bytes>", self.pkcs12.len()))
+            .field("password", &"<redacted>")
+            .finish()
+    }
+}
+
 /// Encapsulates Bosun server connection.
 #[derive(Debug)]
 pub struct BosunClient {
-    /// `<HOSTNAME|IP ADDR>:<PORT>`
+    /// `<HOSTNAME|IP ADDR>:<PORT>` or URL; without scheme, https is used if TLS options are set and http otherwise
     pub host: String,
     /// Timeout for http request connection
     pub timeout: u64,
     pub default_tags: Tags,
     pub username: Option<String>,
     pub password: Option<String>,
+    /// Maximum number of data points sent in a single request by `emit_data`
+    pub max_batch_size: usize,
+    pub retry_policy: RetryPolicy,
+    /// Keeps requests that failed transiently, so they can be replayed later
+    pub spool: Option<Spool>,
+    pub connection: ConnectionConfig,
+    /// HTTP client, lazily created on first use and reused afterwards
+    client: Mutex<Option<reqwest::Client>>,
 }
 
 pub trait Bosun {
     fn emit_metadata(&self, metadata: &Metadata) -> BosunResult;
     fn emit_datum(&self, datum: &Datum) -> BosunResult;
+    /// Emits multiple data points at once; the default implementation emits them one by one.
+    fn emit_data(&self, data: &[Datum]) -> BosunResult {
+        for datum in data {
+            self.emit_datum(datum)?;
+        }
+
+        Ok(())
+    }
     fn set_silence(&self, silence: &Silence) -> BosunResult;
+    /// Lists all silences currently known to Bosun, i.e., active and pending silences.
+    fn list_silences(&self) -> Result<Silences, BosunError>;
+    /// Looks up a single silence by its Bosun id.
+    fn get_silence(&self, id: &str) -> Result<Option<SilenceInfo>, BosunError>;
+    fn clear_silence(&self, id: &str) -> BosunResult;
+    /// Creates an annotation and returns it as stored by Bosun, i.e., including its id.
+    fn create_annotation(&self, annotation: &Annotation) -> Result<Annotation, BosunError>;
+    /// Updates an existing annotation identified by its id.
+    fn update_annotation(&self, annotation: &Annotation) -> Result<Annotation, BosunError>;
+    /// Lists all annotations matching `query`.
+    fn list_annotations(&self, query: &AnnotationQuery) -> Result<Vec<Annotation>, BosunError>;
+    /// Lists all incidents that have not been closed yet.
+    fn open_incidents(&self) -> Result<Vec<Incident>, BosunError>;
+    /// Returns the worst current status of all open incidents carrying all `tags`, or `AlertStatus::Normal` if
+    /// there are none.
+    fn alert_status(&self, tags: &Tags) -> Result<AlertStatus, BosunError> {
+        let status = self
+            .open_incidents()?
+            .iter()
+            .filter(|x| x.has_tags(tags))
+            .map(|x| x.current_status)
+            .max()
+            .unwrap_or(AlertStatus::Normal);
+
+        Ok(status)
+    }
+    /// Evaluates a Bosun expression, e.g., `avg(q("avg:os.cpu{host=*}", "5m", ""))`, at the current time.
+    fn evaluate_expression(&self, expr: &str) -> Result<ExprResult, BosunError>;
     fn send_to_bosun_api(&self, path: &str, json: &str, expected: StatusCode) -> BosunResult;
 }
 
@@ -70,11 +247,50 @@ impl Bosun for BosunClient {
         res
     }
 
+    fn emit_data(&self, data: &[Datum]) -> BosunResult {
+        for chunk in data.chunks(self.max_batch_size.max(1)) {
+            let internal_data: Vec<InternalDatum> = chunk
+                .iter()
+                .map(|datum| {
+                    let mut internal_datum: InternalDatum = datum.into();
+                    internal_datum.add_tags(&self.default_tags);
+                    internal_datum
+                })
+                .collect();
+
+            let encoded = serde_json::to_string(&internal_data)
+                //TODO: Use context to carry original error on
+                .map_err(|_| BosunError::JsonParseError)?;
+            let res = self.send_to_bosun_api("/api/put", &encoded, StatusCode::NO_CONTENT);
+            info!(
+                "Sent {} data to '{:?}' with result: '{:?}'.",
+                internal_data.len(),
+                &self.host,
+                res
+            );
+            res?;
+        }
+
+        Ok(())
+    }
+
     fn set_silence(&self, silence: &Silence) -> BosunResult {
-        let json = serde_json::to_string(silence)
-            //TODO: Use context to carry original error on
-            .map_err(|_| BosunError::JsonParseError)?;
-        let res = self.send_to_bosun_api("/api/silence/set", &json, StatusCode::OK);
+        let path = "/api/silence/set";
+        let json = silence.to_json()?;
+        let requested = Utc::now();
+        let res = self.post_to_bosun_api(path, &json, StatusCode::OK);
+        // Bosun starts a silence without start at the time of the request, so a replayed silence would cover a later
+        // window; spool it with the start pinned to the time of the original request instead.
+        let res = match silence.start {
+            Some(_) => self.spool_transient_error(res, path, &json, StatusCode::OK),
+            None => {
+                let pinned = Silence {
+                    start: Some(requested),
+                    ..silence.clone()
+                };
+                self.spool_transient_error(res, path, &pinned.to_json()?, StatusCode::OK)
+            }
+        };
         info!(
             "Set silence '{:?}' at '{:?}' with result: '{:?}'.",
             json, &self.host, res
@@ -83,38 +299,82 @@ impl Bosun for BosunClient {
         res
     }
 
-    fn send_to_bosun_api(&self, path: &str, json: &str, expected: StatusCode) -> BosunResult {
-        let uri = if self.host.starts_with("http") {
-            format!("{}{}", self.host, path)
-        } else {
-            format!("http://{}{}", self.host, path)
-        };
+    fn list_silences(&self) -> Result<Silences, BosunError> {
+        let json = self.get_from_bosun_api("/api/silence/get")?;
+        let silences: Silences = serde_json::from_str(&json).map_err(|e| BosunError::ReceiveError(format!("{}", e)))?;
+        debug!("Retrieved {} silences from '{:?}'.", silences.len(), &self.host);
 
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(self.timeout))
-            .build()
-            .map_err(|e| BosunError::EmitError(format!("failed to build http client because {}", e.to_string())))?;
+        Ok(silences)
+    }
 
-        let body: Vec<u8> = json.as_bytes().into();
+    fn get_silence(&self, id: &str) -> Result<Option<SilenceInfo>, BosunError> {
+        // Bosun does not offer an endpoint for a single silence, so we have to filter the list.
+        let mut silences = self.list_silences()?;
 
-        let req = client
-            .post(&uri)
-            .header("Content-Type", "application/json; charset=utf-8")
-            .body(body);
+        Ok(silences.remove(id))
+    }
 
-        // Only add basic auth, if username and password are set
-        let req = match (&self.username, &self.password) {
-            (Some(u), p) if !u.is_empty() => req.basic_auth(u, p.clone()),
-            _ => req,
-        };
+    fn clear_silence(&self, id: &str) -> BosunResult {
+        let path = format!("/api/silence/clear?id={}", id);
+        let res = self.send_to_bosun_api(&path, "", StatusCode::OK);
+        info!(
+            "Cleared silence '{}' at '{:?}' with result: '{:?}'.",
+            id, &self.host, res
+        );
 
-        let res = req.send();
+        res
+    }
 
-        match res {
-            Ok(ref response) if response.status() == expected => Ok(()),
-            Ok(response) => Err(BosunError::ReceiveError(format!("{}", response.status()))),
-            Err(err) => Err(BosunError::EmitError(format!("{}", err))),
+    fn create_annotation(&self, annotation: &Annotation) -> Result<Annotation, BosunError> {
+        if !annotation.id.is_empty() {
+            return Err(BosunError::InvalidAnnotation("id is already set"));
+        }
+        self.put_annotation(reqwest::Method::POST, annotation)
+    }
+
+    fn update_annotation(&self, annotation: &Annotation) -> Result<Annotation, BosunError> {
+        if annotation.id.is_empty() {
+            return Err(BosunError::InvalidAnnotation("id is not set"));
         }
+        self.put_annotation(reqwest::Method::PUT, annotation)
+    }
+
+    fn list_annotations(&self, query: &AnnotationQuery) -> Result<Vec<Annotation>, BosunError> {
+        let json = self.get_from_bosun_api_with_query("/api/annotation/query", &query.to_params())?;
+        let annotations: Vec<Annotation> =
+            serde_json::from_str(&json).map_err(|e| BosunError::ReceiveError(format!("{}", e)))?;
+        debug!("Retrieved {} annotations from '{:?}'.", annotations.len(), &self.host);
+
+        Ok(annotations)
+    }
+
+    fn open_incidents(&self) -> Result<Vec<Incident>, BosunError> {
+        let json = self.get_from_bosun_api("/api/incidents/open")?;
+        let incidents: Vec<Incident> =
+            serde_json::from_str(&json).map_err(|e| BosunError::ReceiveError(format!("{}", e)))?;
+        debug!("Retrieved {} open incidents from '{:?}'.", incidents.len(), &self.host);
+
+        Ok(incidents)
+    }
+
+    fn evaluate_expression(&self, expr: &str) -> Result<ExprResult, BosunError> {
+        let json =
+            self.exchange_with_bosun_api(reqwest::Method::POST, "/api/expr", "text/plain; charset=utf-8", expr)?;
+        let result: ExprResult = serde_json::from_str(&json).map_err(|e| BosunError::ReceiveError(format!("{}", e)))?;
+        debug!(
+            "Evaluated expression '{}' at '{:?}' to {} results.",
+            expr,
+            &self.host,
+            result.results.len()
+        );
+
+        Ok(result)
+    }
+
+    fn send_to_bosun_api(&self, path: &str, json: &str, expected: StatusCode) -> BosunResult {
+        let res = self.post_to_bosun_api(path, json, expected);
+
+        self.spool_transient_error(res, path, json, expected)
     }
 }
 
@@ -132,6 +392,11 @@ impl BosunClient {
             default_tags,
             username: None,
             password: None,
+            max_batch_size: DEFAULT_MAX_BATCH_SIZE,
+            retry_policy: RetryPolicy::default(),
+            spool: None,
+            connection: ConnectionConfig::default(),
+            client: Mutex::new(None),
         }
     }
 
@@ -139,6 +404,358 @@ impl BosunClient {
         self.username = Some(username);
         self.password = password;
     }
+
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.retry_policy = retry_policy;
+    }
+
+    /// Spools requests that fail transiently instead of returning an error; see `replay_spool`.
+    pub fn set_spool(&mut self, spool: Spool) {
+        self.spool = Some(spool);
+    }
+
+    /// Sets transport options; the options are validated by building a new HTTP client right away.
+    pub fn set_connection(&mut self, connection: ConnectionConfig) -> Result<(), BosunError> {
+        let client = build_http_client(self.timeout, &connection)?;
+        self.connection = connection;
+        *self
+            .client
+            .lock()
+            .expect("Could not retrieve Mutex lock for http client") = Some(client);
+
+        Ok(())
+    }
+
+    /// Sends all spooled requests to Bosun again and returns the number of successfully replayed requests.
+    ///
+    /// Requests that fail transiently again are kept in the spool; requests Bosun rejects are dropped.
+    pub fn replay_spool(&self) -> Result<usize, BosunError> {
+        let spool = match self.spool {
+            Some(ref spool) => spool,
+            None => return Ok(0),
+        };
+
+        let requests = spool.take()?;
+        let mut replayed = 0;
+        for request in requests {
+            let expected = StatusCode::from_u16(request.expected).unwrap_or(StatusCode::OK);
+            match self.post_to_bosun_api(&request.path, &request.body, expected) {
+                Ok(_) => replayed += 1,
+                Err(ref err) if err.is_transient() => spool.push(&request)?,
+                Err(err) => warn!("Dropping spooled request to '{}' because {}.", request.path, err),
+            }
+        }
+        info!("Replayed {} spooled requests to '{:?}'.", replayed, &self.host);
+
+        Ok(replayed)
+    }
+
+    fn uri_for(&self, path: &str) -> String {
+        if self.host.starts_with("http") {
+            format!("{}{}", self.host, path)
+        } else if self.connection.uses_tls() {
+            format!("https://{}{}", self.host, path)
+        } else {
+            format!("http://{}{}", self.host, path)
+        }
+    }
+
+    fn http_client(&self) -> Result<reqwest::Client, BosunError> {
+        let mut client = self
+            .client
+            .lock()
+            .expect("Could not retrieve Mutex lock for http client");
+        if let Some(ref client) = *client {
+            // reqwest's client is reference counted, so cloning shares its connection pool.
+            return Ok(client.clone());
+        }
+
+        let new_client = build_http_client(self.timeout, &self.connection)?;
+        *client = Some(new_client.clone());
+
+        Ok(new_client)
+    }
+
+    /// Sends a GET request to Bosun and returns the response body.
+    pub fn get_from_bosun_api(&self, path: &str) -> Result<String, BosunError> {
+        self.get_from_bosun_api_with_query(path, &[])
+    }
+
+    /// Sends a GET request with URL encoded query parameters to Bosun and returns the response body.
+    pub fn get_from_bosun_api_with_query(&self, path: &str, query: &[(&str, String)]) -> Result<String, BosunError> {
+        let uri = self.uri_for(path);
+        let client = self.http_client()?;
+
+        let res = self.send_with_retry(&reqwest::Method::GET, path, || {
+            self.with_basic_auth(client.get(&uri).query(query))
+        });
+
+        match res {
+            Ok(mut response) if response.status() == StatusCode::OK => response
+                .text()
+                .map_err(|e| BosunError::ReceiveError(format!("failed to read response body because {}", e))),
+            res => Err(to_bosun_error(res)),
+        }
+    }
+
+    /// Spools the request `json` to `path`, if `res` failed transiently and a spool is set.
+    fn spool_transient_error(&self, res: BosunResult, path: &str, json: &str, expected: StatusCode) -> BosunResult {
+        match (res, &self.spool) {
+            (Err(ref err), Some(spool)) if err.is_transient() => {
+                let request = SpooledRequest {
+                    path: path.to_string(),
+                    body: json.to_string(),
+                    expected: expected.as_u16(),
+                };
+                spool.push(&request)?;
+                warn!("Spooled request to '{}' because {}.", path, err);
+                Ok(())
+            }
+            (res, _) => res,
+        }
+    }
+
+    fn post_to_bosun_api(&self, path: &str, json: &str, expected: StatusCode) -> BosunResult {
+        let uri = self.uri_for(path);
+        let client = self.http_client()?;
+
+        let res = self.send_with_retry(&reqwest::Method::POST, path, || {
+            let body: Vec<u8> = json.as_bytes().into();
+            let req = client
+                .post(&uri)
+                .header("Content-Type", "application/json; charset=utf-8")
+                .body(body);
+            self.with_basic_auth(req)
+        });
+
+        match res {
+            Ok(ref response) if response.status() == expected => Ok(()),
+            res => Err(to_bosun_error(res)),
+        }
+    }
+
+    /// Creates (POST) or updates (PUT) an annotation; Bosun answers with the stored annotation.
+    ///
+    /// Annotations are not spooled, because the caller needs the id Bosun assigns.
+    fn put_annotation(&self, method: reqwest::Method, annotation: &Annotation) -> Result<Annotation, BosunError> {
+        let json = serde_json::to_string(annotation).map_err(|_| BosunError::JsonParseError)?;
+        let res = self
+            .exchange_with_bosun_api(method, "/api/annotation", "application/json; charset=utf-8", &json)
+            .and_then(|body| {
+                serde_json::from_str::<Annotation>(&body)
+                    .map_err(|e| BosunError::ReceiveError(format!("failed to parse annotation because {}", e)))
+            });
+        info!(
+            "Sent annotation '{:?}' to '{:?}' with result: '{:?}'.",
+            json, &self.host, res
+        );
+
+        res
+    }
+
+    /// Sends `body` to Bosun and returns the response body, if Bosun answers with 200.
+    fn exchange_with_bosun_api(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        content_type: &str,
+        body: &str,
+    ) -> Result<String, BosunError> {
+        let uri = self.uri_for(path);
+        let client = self.http_client()?;
+
+        let res = self.send_with_retry(&method, path, || {
+            let body: Vec<u8> = body.as_bytes().into();
+            let req = client
+                .request(method.clone(), &uri)
+                .header("Content-Type", content_type)
+                .body(body);
+            self.with_basic_auth(req)
+        });
+
+        match res {
+            Ok(mut response) if response.status() == StatusCode::OK => response
+                .text()
+                .map_err(|e| BosunError::ReceiveError(format!("failed to read response body because {}", e))),
+            res => Err(to_bosun_error(res)),
+        }
+    }
+
+    fn with_basic_auth(&self, req: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        // Only add basic auth, if username and password are set
+        match (&self.username, &self.password) {
+            (Some(u), p) if !u.is_empty() => req.basic_auth(u, p.clone()),
+            _ => req,
+        }
+    }
+
+    /// Sends the request built by `request` and retries it according to the retry policy as long as Bosun answers
+    /// with 5xx or the request times out.
+    ///
+    /// Only idempotent requests are retried, because Bosun might have processed a request that timed out or failed
+    /// with 5xx anyway, e.g., retrying an annotation creation might create the annotation twice.
+    fn send_with_retry<F>(
+        &self,
+        method: &reqwest::Method,
+        path: &str,
+        request: F,
+    ) -> Result<reqwest::Response, reqwest::Error>
+    where
+        F: Fn() -> reqwest::RequestBuilder,
+    {
+        let max_retries = if is_idempotent(method, path) {
+            self.retry_policy.max_retries
+        } else {
+            0
+        };
+        let mut retry = 0;
+        loop {
+            let res = request().send();
+            let retryable = match res {
+                Ok(ref response) => response.status().is_server_error(),
+                Err(ref err) => err.is_timeout(),
+            };
+            if !retryable || retry >= max_retries {
+                return res;
+            }
+
+            let backoff = self.retry_policy.backoff(retry);
+            warn!(
+                "Request to '{:?}' failed ({:?}); retrying in {} ms.",
+                &self.host,
+                res.as_ref().map(reqwest::Response::status),
+                backoff.as_millis()
+            );
+            std::thread::sleep(backoff);
+            retry += 1;
+        }
+    }
+}
+
+/// Bosun API endpoints that accept POST requests, but may be safely sent more than once.
+const IDEMPOTENT_POST_PATHS: &[&str] = &["/api/put", "/api/metadata/put", "/api/silence/clear", "/api/expr"];
+
+/// Returns whether sending a request more than once has the same effect as sending it once.
+///
+/// Setting a silence is not idempotent, because Bosun derives the silence's start from the time of the request if it
+/// is not set.
+fn is_idempotent(method: &reqwest::Method, path: &str) -> bool {
+    match *method {
+        reqwest::Method::GET | reqwest::Method::HEAD | reqwest::Method::PUT | reqwest::Method::DELETE => true,
+        reqwest::Method::POST => {
+            let path = path.split('?').next().unwrap_or(path);
+            IDEMPOTENT_POST_PATHS.contains(&path)
+        }
+        _ => false,
+    }
+}
+
+fn build_http_client(timeout: u64, connection: &ConnectionConfig) -> Result<reqwest::Client, BosunError> {
+    let invalid = |what: &str, e: reqwest::Error| BosunError::InvalidConnection(format!("{} because {}", what, e));
+
+    let mut builder = reqwest::Client::builder().timeout(Duration::from_secs(timeout));
+    if let Some(connect_timeout) = connection.connect_timeout {
+        builder = builder.connect_timeout(connect_timeout);
+    }
+    if let Some(ref proxy) = connection.proxy {
+        let proxy = reqwest::Proxy::all(proxy.as_str()).map_err(|e| invalid("failed to parse proxy URL", e))?;
+        builder = builder.proxy(proxy);
+    }
+    for pem in &connection.ca_certificates {
+        let cert = reqwest::Certificate::from_pem(pem).map_err(|e| invalid("failed to parse CA certificate", e))?;
+        builder = builder.add_root_certificate(cert);
+    }
+    if let Some(ref identity) = connection.client_identity {
+        let identity = reqwest::Identity::from_pkcs12_der(&identity.pkcs12, &identity.password)
+            .map_err(|e| invalid("failed to parse client identity", e))?;
+        builder = builder.identity(identity);
+    }
+
+    builder.build().map_err(|e| invalid("failed to build http client", e))
+}
+
+fn to_bosun_error(res: Result<reqwest::Response, reqwest::Error>) -> BosunError {
+    match res {
+        Ok(ref response) if response.status().is_server_error() => {
+            BosunError::Unavailable(format!("{}", response.status()))
+        }
+        Ok(response) => BosunError::ReceiveError(format!("{}", response.status())),
+        Err(err) => BosunError::EmitError(format!("{}", err)),
+    }
+}
+
+/// Metric rate type as understood by Bosun.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Rate {
+    /// Value at a point in time
+    Gauge,
+    /// Monotonically increasing value
+    Counter,
+    /// Value that already is a rate, e.g., a change per event
+    Rate,
+}
+
+impl Rate {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Rate::Gauge => "gauge",
+            Rate::Counter => "counter",
+            Rate::Rate => "rate",
+        }
+    }
+}
+
+impl std::fmt::Display for Rate {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Metric unit; Bosun displays units as they are, so these are the units our metrics use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum Unit {
+    Bytes,
+    Change,
+    Count,
+    Credentials,
+    Days,
+    Event,
+    Health,
+    Instances,
+    Invocations,
+    Percent,
+    Result,
+    Scaling,
+    Seconds,
+    State,
+}
+
+impl Unit {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Unit::Bytes => "Bytes",
+            Unit::Change => "Change",
+            Unit::Count => "Count",
+            Unit::Credentials => "Credentials",
+            Unit::Days => "Days",
+            Unit::Event => "Event",
+            Unit::Health => "Health",
+            Unit::Instances => "Instances",
+            Unit::Invocations => "Invocations",
+            Unit::Percent => "Percent",
+            Unit::Result => "Result",
+            Unit::Scaling => "Scaling",
+            Unit::Seconds => "Seconds",
+            Unit::State => "State",
+        }
+    }
+}
+
+impl std::fmt::Display for Unit {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -146,18 +763,17 @@ impl BosunClient {
 pub struct Metadata<'a> {
     /// Metric name
     pub metric: &'a str,
-    /// Metric rate type: [gauge, counter rate]
-    pub rate: &'a str,
+    /// Metric rate type
+    pub rate: Rate,
     /// Metric unit
-    pub unit: &'a str,
+    pub unit: Unit,
     /// Metric description
     pub description: &'a str,
 }
 
-// TODO: Add check for rate type: [gauge, counter rate]
 impl<'a> Metadata<'a> {
     /// Creates new metric meta data.
-    pub fn new(metric: &'a str, rate: &'a str, unit: &'a str, description: &'a str) -> Metadata<'a> {
+    pub fn new(metric: &'a str, rate: Rate, unit: Unit, description: &'a str) -> Metadata<'a> {
         Metadata {
             metric,
             rate,
@@ -170,10 +786,10 @@ impl<'a> Metadata<'a> {
         let mut metadata = [HashMap::new(), HashMap::new(), HashMap::new()];
         metadata[0].insert("metric", self.metric);
         metadata[0].insert("name", "unit");
-        metadata[0].insert("value", self.unit);
+        metadata[0].insert("value", self.unit.as_str());
         metadata[1].insert("metric", self.metric);
         metadata[1].insert("name", "rate");
-        metadata[1].insert("value", self.rate);
+        metadata[1].insert("value", self.rate.as_str());
         metadata[2].insert("metric", self.metric);
         metadata[2].insert("name", "desc");
         metadata[2].insert("value", self.description);
@@ -294,7 +910,102 @@ impl<'a> InternalDatum<'a> {
     }
 }
 
-#[derive(Debug, Serialize)]
+/// Buffers data points and emits them in batches via `Bosun::emit_data`.
+///
+/// The buffer is flushed when it reaches its capacity, on `flush`, and when the emitter is dropped. Errors while
+/// flushing on drop can only be logged, so call `flush` explicitly if you care about the result.
+pub struct BufferedEmitter<'a, B: Bosun + ?Sized> {
+    bosun: &'a B,
+    capacity: usize,
+    buffer: Vec<OwnedDatum>,
+}
+
+impl<'a, B: Bosun + ?Sized> BufferedEmitter<'a, B> {
+    /// Creates a new emitter that flushes after `DEFAULT_MAX_BATCH_SIZE` data points.
+    pub fn new(bosun: &'a B) -> BufferedEmitter<'a, B> {
+        Self::with_capacity(bosun, DEFAULT_MAX_BATCH_SIZE)
+    }
+
+    /// Creates a new emitter that flushes after `capacity` data points.
+    pub fn with_capacity(bosun: &'a B, capacity: usize) -> BufferedEmitter<'a, B> {
+        let capacity = capacity.max(1);
+        BufferedEmitter {
+            bosun,
+            capacity,
+            buffer: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Adds a copy of `datum` to the buffer and flushes the buffer if it is full.
+    pub fn emit(&mut self, datum: &Datum) -> BosunResult {
+        self.buffer.push(datum.into());
+        if self.buffer.len() >= self.capacity {
+            self.flush()
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Number of buffered data points.
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    /// Emits all buffered data points; the buffer is emptied even if emitting fails.
+    pub fn flush(&mut self) -> BosunResult {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+
+        let buffer = std::mem::replace(&mut self.buffer, Vec::with_capacity(self.capacity));
+        let data: Vec<Datum> = buffer.iter().map(OwnedDatum::as_datum).collect();
+        debug!("Flushing {} buffered data.", data.len());
+
+        self.bosun.emit_data(&data)
+    }
+}
+
+impl<'a, B: Bosun + ?Sized> Drop for BufferedEmitter<'a, B> {
+    fn drop(&mut self) {
+        if let Err(err) = self.flush() {
+            error!("Failed to flush buffered data on drop because {}.", err);
+        }
+    }
+}
+
+/// Owned copy of a `Datum` so that buffered data points do not need to outlive their origin.
+#[derive(Debug)]
+struct OwnedDatum {
+    metric: String,
+    timestamp: i64,
+    value: String,
+    tags: Tags,
+}
+
+impl OwnedDatum {
+    fn as_datum(&self) -> Datum<'_> {
+        Datum::new(&self.metric, self.timestamp, &self.value, &self.tags)
+    }
+}
+
+impl<'a> From<&'a Datum<'a>> for OwnedDatum {
+    fn from(datum: &'a Datum<'a>) -> OwnedDatum {
+        OwnedDatum {
+            metric: datum.metric.to_string(),
+            timestamp: datum.timestamp,
+            value: datum.value.to_string(),
+            tags: datum.tags.clone(),
+        }
+    }
+}
+
+/// Format Bosun expects for absolute silence start and end times, cf. `tsdbFormatSecs` in Bosun.
+const SILENCE_TIME_FORMAT: &str = "%Y/%m/%d-%H:%M:%S";
+
 // cf. https://github.com/bosun-monitor/bosun/blob/master/models/silence.go#L12. 28.11.2018
 // Start, End time.Time
 // Alert      string
@@ -305,78 +1016,629 @@ impl<'a> InternalDatum<'a> {
 // Message    string
 // {"duration":"24h","tags":"host=doc-server-i-lukas","forget":null,"message":"Server has been
 // terminated by ASG."}
+/// Represents a silence to set in Bosun; use `Silence::builder` to create one.
+///
+/// Bosun parses the silence request into a map of strings, so all values -- including bools -- are serialized as
+/// strings. Empty values are omitted, because Bosun treats absent and empty values alike.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct Silence {
-    duration: String,
-    tags: String,
-    /// Bosun does not like bool, only Strings "true" or "false"
-    forget: String,
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "silence_time")]
+    start: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "silence_time")]
+    end: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    alert: Option<String>,
+    #[serde(serialize_with = "tag_string")]
+    tags: Tags,
+    #[serde(serialize_with = "bool_string")]
+    forget: bool,
     user: String,
     message: String,
-    /// Bosun does not like bool, only Strings "true" or "false"
-    confirm: String,
+    // Bosun only checks for a non-empty value; without it, Bosun just returns the alerts that would be silenced.
+    #[serde(skip_serializing_if = "is_false", serialize_with = "bool_string")]
+    confirm: bool,
+}
+
+fn silence_time<S: Serializer>(time: &Option<DateTime<Utc>>, serializer: S) -> Result<S::Ok, S::Error> {
+    match time {
+        Some(time) => serializer.collect_str(&time.format(SILENCE_TIME_FORMAT)),
+        None => serializer.serialize_none(),
+    }
+}
+
+fn tag_string<S: Serializer>(tags: &Tags, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&join_tags(tags))
+}
+
+fn join_tags(tags: &Tags) -> String {
+    let mut tags: Vec<_> = tags.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+    tags.sort();
+    tags.join(",")
+}
+
+fn bool_string<S: Serializer>(value: &bool, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
 }
 
 impl Silence {
-    pub fn host(host: &str, duration: &str) -> Silence {
-        Silence {
-            // TODO: These parameters should be config parameters
-            duration: duration.to_string(),
-            tags: format!("host={}", host),
-            forget: "true".to_string(),
-            user: "kevin.lambda".to_string(),
-            message: "Host has been terminated by ASG.".to_string(),
-            confirm: "true".to_string(),
+    /// Creates a new, empty `SilenceBuilder`.
+    pub fn builder() -> SilenceBuilder {
+        SilenceBuilder::default()
+    }
+
+    pub fn start(&self) -> Option<DateTime<Utc>> {
+        self.start
+    }
+
+    pub fn end(&self) -> Option<DateTime<Utc>> {
+        self.end
+    }
+
+    pub fn duration(&self) -> Option<&str> {
+        self.duration.as_deref()
+    }
+
+    pub fn alert(&self) -> Option<&str> {
+        self.alert.as_deref()
+    }
+
+    pub fn tags(&self) -> &Tags {
+        &self.tags
+    }
+
+    pub fn forget(&self) -> bool {
+        self.forget
+    }
+
+    pub fn user(&self) -> &str {
+        &self.user
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn confirm(&self) -> bool {
+        self.confirm
+    }
+
+    /// Returns the tags in Bosun's tag string format, e.g. `host=webserver-*,service=nginx`.
+    pub fn tag_string(&self) -> String {
+        join_tags(&self.tags)
+    }
+
+    pub fn to_json(&self) -> Result<String, BosunError> {
+        let json = serde_json::to_string(&self)
+            //TODO: Use context to carry original error on
+            .map_err(|_| BosunError::JsonParseError)?;
+        debug!("Silence::to_json '{:?}', '{:?}'.", &self, json);
+
+        Ok(json)
+    }
+}
+
+/// Builds a `Silence` step by step.
+///
+/// A silence requires at least an alert or one tag to match, an end time or a duration, and a user. By default a
+/// silence is confirmed, i.e., actually set by Bosun, and not forgotten after it expires.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SilenceBuilder {
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+    duration: Option<String>,
+    alert: Option<String>,
+    tags: Tags,
+    forget: bool,
+    user: Option<String>,
+    message: Option<String>,
+    confirm: bool,
+}
+
+impl Default for SilenceBuilder {
+    fn default() -> SilenceBuilder {
+        SilenceBuilder {
+            start: None,
+            end: None,
+            duration: None,
+            alert: None,
+            tags: Tags::new(),
+            forget: false,
+            user: None,
+            message: None,
+            confirm: true,
+        }
+    }
+}
+
+impl SilenceBuilder {
+    /// Silences alerts with tag `key` matching `value`; `value` may contain Bosun's wildcard `*`.
+    pub fn tag<S: Into<String>, T: Into<String>>(mut self, key: S, value: T) -> Self {
+        self.tags.insert(key.into(), value.into());
+        self
+    }
+
+    /// Adds all `tags` to the silence.
+    pub fn tags(mut self, tags: &Tags) -> Self {
+        self.tags.extend(tags.iter().map(|(k, v)| (k.clone(), v.clone())));
+        self
+    }
+
+    /// Shortcut for `tag("host", host)`.
+    pub fn host<S: Into<String>>(self, host: S) -> Self {
+        self.tag("host", host)
+    }
+
+    /// Silences only the alert with this name.
+    pub fn alert<S: Into<String>>(mut self, alert: S) -> Self {
+        self.alert = Some(alert.into());
+        self
+    }
+
+    /// Starts the silence at `start` instead of now.
+    pub fn start(mut self, start: DateTime<Utc>) -> Self {
+        self.start = Some(start);
+        self
+    }
+
+    /// Ends the silence at `end`; mutually exclusive with `duration`.
+    pub fn end(mut self, end: DateTime<Utc>) -> Self {
+        self.end = Some(end);
+        self
+    }
+
+    /// Ends the silence after `duration` in Bosun's duration format, e.g. `15m`, `24h`, or `1d`; mutually
+    /// exclusive with `end`.
+    pub fn duration<S: Into<String>>(mut self, duration: S) -> Self {
+        self.duration = Some(duration.into());
+        self
+    }
+
+    /// Forget alerts that are silenced and have been closed, e.g., because the host has been removed.
+    pub fn forget(mut self, forget: bool) -> Self {
+        self.forget = forget;
+        self
+    }
+
+    /// If `false`, Bosun does not set the silence but only reports which alerts would be silenced.
+    pub fn confirm(mut self, confirm: bool) -> Self {
+        self.confirm = confirm;
+        self
+    }
+
+    pub fn user<S: Into<String>>(mut self, user: S) -> Self {
+        self.user = Some(user.into());
+        self
+    }
+
+    pub fn message<S: Into<String>>(mut self, message: S) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+
+    pub fn build(self) -> Result<Silence, BosunError> {
+        if self.tags.is_empty() && self.alert.is_none() {
+            return Err(BosunError::InvalidSilence("neither alert nor tags are set"));
         }
+        match (self.end, &self.duration) {
+            (None, None) => return Err(BosunError::InvalidSilence("neither end nor duration is set")),
+            (Some(_), Some(_)) => return Err(BosunError::InvalidSilence("both end and duration are set")),
+            _ => {}
+        }
+        if let (Some(start), Some(end)) = (self.start, self.end) {
+            if end <= start {
+                return Err(BosunError::InvalidSilence("end is not after start"));
+            }
+        }
+        let user = match self.user {
+            Some(user) if !user.is_empty() => user,
+            _ => return Err(BosunError::InvalidSilence("user is not set")),
+        };
+
+        Ok(Silence {
+            start: self.start,
+            end: self.end,
+            duration: self.duration,
+            alert: self.alert,
+            tags: self.tags,
+            forget: self.forget,
+            user,
+            message: self.message.unwrap_or_default(),
+            confirm: self.confirm,
+        })
+    }
+}
+
+/// Silences as returned by Bosun, indexed by silence id.
+pub type Silences = HashMap<String, SilenceInfo>;
+
+/// Represents a silence as stored by Bosun.
+// cf. https://github.com/bosun-monitor/bosun/blob/master/models/silence.go#L12
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+pub struct SilenceInfo {
+    #[serde(rename = "Start")]
+    pub start: DateTime<Utc>,
+    #[serde(rename = "End")]
+    pub end: DateTime<Utc>,
+    #[serde(rename = "Alert")]
+    pub alert: String,
+    #[serde(rename = "Tags", default)]
+    pub tags: Option<Tags>,
+    #[serde(rename = "TagString")]
+    pub tag_string: String,
+    #[serde(rename = "Forget")]
+    pub forget: bool,
+    #[serde(rename = "User")]
+    pub user: String,
+    #[serde(rename = "Message")]
+    pub message: String,
+}
+
+impl SilenceInfo {
+    /// Checks if this silence matches the tag `key` with `value` exactly.
+    pub fn has_tag(&self, key: &str, value: &str) -> bool {
+        self.tags
+            .as_ref()
+            .and_then(|tags| tags.get(key))
+            .map(|x| x == value)
+            .unwrap_or(false)
+    }
+
+    /// Checks if this silence is active at the time of `now`.
+    pub fn is_active_at(&self, now: DateTime<Utc>) -> bool {
+        self.start <= now && now < self.end
     }
 }
 
 pub mod testing {
+    //! A recording Bosun mock for tests.
+    //!
+    //! The mock records every metadata, datum, and silence it receives and may be scripted to fail specific calls,
+    //! e.g., to simulate Bosun being unavailable.
+
     use super::*;
 
     use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
+    /// Calls of the `Bosun` trait that can be counted and scripted to fail.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum Call {
+        Metadata,
+        /// Every single datum, whether emitted by `emit_datum` or as part of `emit_data`
+        Datum,
+        EmitData,
+        SetSilence,
+        ListSilences,
+        GetSilence,
+        ClearSilence,
+        CreateAnnotation,
+        UpdateAnnotation,
+        ListAnnotations,
+        OpenIncidents,
+        EvaluateExpression,
+    }
+
+    impl Call {
+        fn key(self) -> &'static str {
+            match self {
+                Call::Metadata => "metadata",
+                Call::Datum => "datum",
+                Call::EmitData => "emit_data",
+                Call::SetSilence => "set_silence",
+                Call::ListSilences => "list_silences",
+                Call::GetSilence => "get_silence",
+                Call::ClearSilence => "clear_silence",
+                Call::CreateAnnotation => "create_annotation",
+                Call::UpdateAnnotation => "update_annotation",
+                Call::ListAnnotations => "list_annotations",
+                Call::OpenIncidents => "open_incidents",
+                Call::EvaluateExpression => "evaluate_expression",
+            }
+        }
+    }
+
+    /// Owned copy of emitted metadata.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct RecordedMetadata {
+        pub metric: String,
+        pub rate: Rate,
+        pub unit: Unit,
+        pub description: String,
+    }
+
+    /// Owned copy of an emitted datum.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct RecordedDatum {
+        pub metric: String,
+        pub timestamp: i64,
+        pub value: String,
+        pub tags: Tags,
+    }
+
+    impl RecordedDatum {
+        pub fn has_tag(&self, key: &str, value: &str) -> bool {
+            self.tags.get(key).map(|x| x == value).unwrap_or(false)
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct ScriptedFailure {
+        call: Call,
+        /// Fails the n-th call, starting at 1; `None` fails all calls
+        nth: Option<u32>,
+        error: BosunError,
+    }
+
     #[derive(PartialEq, Eq, Debug)]
     pub struct BosunMockClient {
         pub stats: Rc<RefCell<HashMap<&'static str, u32>>>,
+        pub silences: Rc<RefCell<Silences>>,
+        pub metadata: Rc<RefCell<Vec<RecordedMetadata>>>,
+        pub data: Rc<RefCell<Vec<RecordedDatum>>>,
+        pub set_silences: Rc<RefCell<Vec<Silence>>>,
+        /// Created annotations in order of creation; updates replace annotations in place
+        pub annotations: Rc<RefCell<Vec<Annotation>>>,
+        pub incidents: Rc<RefCell<Vec<Incident>>>,
+        /// Results by expression
+        pub expressions: Rc<RefCell<HashMap<String, ExprResult>>>,
+        failures: Rc<RefCell<Vec<ScriptedFailure>>>,
     }
 
     impl Default for BosunMockClient {
         fn default() -> BosunMockClient {
             BosunMockClient {
                 stats: Rc::new(RefCell::new(HashMap::new())),
+                silences: Rc::new(RefCell::new(Silences::new())),
+                metadata: Rc::new(RefCell::new(Vec::new())),
+                data: Rc::new(RefCell::new(Vec::new())),
+                set_silences: Rc::new(RefCell::new(Vec::new())),
+                annotations: Rc::new(RefCell::new(Vec::new())),
+                incidents: Rc::new(RefCell::new(Vec::new())),
+                expressions: Rc::new(RefCell::new(HashMap::new())),
+                failures: Rc::new(RefCell::new(Vec::new())),
             }
         }
     }
 
     impl BosunMockClient {
-        fn inc(&self, key: &'static str) {
-            let mut stats = self.stats.borrow_mut();
-            let count = stats.get(key).unwrap_or(&0) + 1;
-            stats.insert(key, count);
+        /// Creates a mock client that already knows the given silences.
+        pub fn with_silences(silences: Silences) -> BosunMockClient {
+            BosunMockClient {
+                silences: Rc::new(RefCell::new(silences)),
+                ..Default::default()
+            }
+        }
+
+        /// Creates a mock client that sees the given open incidents.
+        pub fn with_incidents(incidents: Vec<Incident>) -> BosunMockClient {
+            BosunMockClient {
+                incidents: Rc::new(RefCell::new(incidents)),
+                ..Default::default()
+            }
+        }
+
+        /// Lets `evaluate_expression` return `result` for `expr`.
+        pub fn with_expression_result<S: Into<String>>(self, expr: S, result: ExprResult) -> Self {
+            self.expressions.borrow_mut().insert(expr.into(), result);
+            self
+        }
+
+        /// Lets the `nth` call, starting at 1, of `call` fail with `error`.
+        pub fn fail_nth(self, call: Call, nth: u32, error: BosunError) -> Self {
+            self.failures.borrow_mut().push(ScriptedFailure {
+                call,
+                nth: Some(nth),
+                error,
+            });
+            self
+        }
+
+        /// Lets all calls of `call` fail with `error`.
+        pub fn fail_always(self, call: Call, error: BosunError) -> Self {
+            self.failures
+                .borrow_mut()
+                .push(ScriptedFailure { call, nth: None, error });
+            self
+        }
+
+        /// Number of calls of `call` so far, including failed ones.
+        pub fn calls(&self, call: Call) -> u32 {
+            *self.stats.borrow().get(call.key()).unwrap_or(&0)
+        }
+
+        /// All successfully emitted data for `metric`.
+        pub fn data_for(&self, metric: &str) -> Vec<RecordedDatum> {
+            self.data
+                .borrow()
+                .iter()
+                .filter(|x| x.metric == metric)
+                .cloned()
+                .collect()
+        }
+
+        /// The last successfully emitted datum for `metric` with tag `key` set to `value`.
+        pub fn datum_with_tag(&self, metric: &str, key: &str, value: &str) -> Option<RecordedDatum> {
+            self.data
+                .borrow()
+                .iter()
+                .rev()
+                .find(|x| x.metric == metric && x.has_tag(key, value))
+                .cloned()
+        }
+
+        /// The last successfully emitted metadata for `metric`.
+        pub fn metadata_for(&self, metric: &str) -> Option<RecordedMetadata> {
+            self.metadata
+                .borrow()
+                .iter()
+                .rev()
+                .find(|x| x.metric == metric)
+                .cloned()
+        }
+
+        /// The last successfully set silence with tag `key` set to `value`.
+        pub fn silence_with_tag(&self, key: &str, value: &str) -> Option<Silence> {
+            self.set_silences
+                .borrow()
+                .iter()
+                .rev()
+                .find(|x| x.tags().get(key).map(|v| v == value).unwrap_or(false))
+                .cloned()
+        }
+
+        /// All annotations of `category`.
+        pub fn annotations_for(&self, category: &str) -> Vec<Annotation> {
+            self.annotations
+                .borrow()
+                .iter()
+                .filter(|x| x.category == category)
+                .cloned()
+                .collect()
+        }
+
+        /// Counts `call` and returns the scripted failure for this call, if any.
+        fn call(&self, call: Call) -> BosunResult {
+            let count = {
+                let mut stats = self.stats.borrow_mut();
+                let count = stats.entry(call.key()).or_insert(0);
+                *count += 1;
+                *count
+            };
+
+            let failures = self.failures.borrow();
+            let failure = failures
+                .iter()
+                .find(|x| x.call == call && x.nth.map(|nth| nth == count).unwrap_or(true));
+            match failure {
+                Some(failure) => Err(failure.error.clone()),
+                None => Ok(()),
+            }
+        }
+
+        fn record_datum(&self, datum: &Datum) -> BosunResult {
+            self.call(Call::Datum)?;
+            self.data.borrow_mut().push(RecordedDatum {
+                metric: datum.metric.to_string(),
+                timestamp: datum.timestamp,
+                value: datum.value.to_string(),
+                tags: datum.tags.clone(),
+            });
+            Ok(())
         }
     }
 
     impl Bosun for BosunMockClient {
-        fn emit_metadata(&self, _: &Metadata) -> BosunResult {
-            self.inc("metadata");
+        fn emit_metadata(&self, metadata: &Metadata) -> BosunResult {
+            self.call(Call::Metadata)?;
+            self.metadata.borrow_mut().push(RecordedMetadata {
+                metric: metadata.metric.to_string(),
+                rate: metadata.rate,
+                unit: metadata.unit,
+                description: metadata.description.to_string(),
+            });
             Ok(())
         }
 
-        fn emit_datum(&self, _: &Datum) -> BosunResult {
-            self.inc("datum");
+        fn emit_datum(&self, datum: &Datum) -> BosunResult {
+            self.record_datum(datum)
+        }
+
+        fn emit_data(&self, data: &[Datum]) -> BosunResult {
+            self.call(Call::EmitData)?;
+            for datum in data {
+                self.record_datum(datum)?;
+            }
             Ok(())
         }
 
-        fn set_silence(&self, _: &Silence) -> BosunResult {
-            self.inc("set_silence");
+        fn set_silence(&self, silence: &Silence) -> BosunResult {
+            self.call(Call::SetSilence)?;
+            self.set_silences.borrow_mut().push(silence.clone());
             Ok(())
         }
 
+        fn list_silences(&self) -> Result<Silences, BosunError> {
+            self.call(Call::ListSilences)?;
+            Ok(self.silences.borrow().clone())
+        }
+
+        fn get_silence(&self, id: &str) -> Result<Option<SilenceInfo>, BosunError> {
+            self.call(Call::GetSilence)?;
+            Ok(self.silences.borrow().get(id).cloned())
+        }
+
+        fn clear_silence(&self, id: &str) -> BosunResult {
+            self.call(Call::ClearSilence)?;
+            self.silences.borrow_mut().remove(id);
+            Ok(())
+        }
+
+        fn create_annotation(&self, annotation: &Annotation) -> Result<Annotation, BosunError> {
+            self.call(Call::CreateAnnotation)?;
+            let mut annotations = self.annotations.borrow_mut();
+            let mut created = annotation.clone();
+            created.id = format!("{}", annotations.len() + 1);
+            annotations.push(created.clone());
+            Ok(created)
+        }
+
+        fn update_annotation(&self, annotation: &Annotation) -> Result<Annotation, BosunError> {
+            self.call(Call::UpdateAnnotation)?;
+            let mut annotations = self.annotations.borrow_mut();
+            match annotations.iter_mut().find(|x| x.id == annotation.id) {
+                Some(existing) => {
+                    *existing = annotation.clone();
+                    Ok(annotation.clone())
+                }
+                None => Err(BosunError::ReceiveError("404 Not Found".to_string())),
+            }
+        }
+
+        fn list_annotations(&self, query: &AnnotationQuery) -> Result<Vec<Annotation>, BosunError> {
+            self.call(Call::ListAnnotations)?;
+            Ok(self
+                .annotations
+                .borrow()
+                .iter()
+                .filter(|x| query.matches(x))
+                .cloned()
+                .collect())
+        }
+
+        fn open_incidents(&self) -> Result<Vec<Incident>, BosunError> {
+            self.call(Call::OpenIncidents)?;
+            Ok(self.incidents.borrow().clone())
+        }
+
+        fn evaluate_expression(&self, expr: &str) -> Result<ExprResult, BosunError> {
+            self.call(Call::EvaluateExpression)?;
+            self.expressions
+                .borrow()
+                .get(expr)
+                .cloned()
+                .ok_or_else(|| BosunError::ReceiveError(format!("unknown expression '{}'", expr)))
+        }
+
         fn send_to_bosun_api(&self, _: &str, _: &str, _: StatusCode) -> BosunResult {
             Ok(())
         }
     }
 
+    impl sink::MetricSink for BosunMockClient {
+        fn emit_metadata(&self, metadata: &Metadata) -> BosunResult {
+            Bosun::emit_metadata(self, metadata)
+        }
+
+        fn emit_data(&self, data: &[Datum]) -> BosunResult {
+            Bosun::emit_data(self, data)
+        }
+    }
+
     #[derive(PartialEq, Eq, Debug)]
     pub struct BosunCallStats {
         pub metadata_count: u32,
@@ -396,12 +1658,10 @@ pub mod testing {
 
     impl BosunMockClient {
         pub fn to_stats(&self) -> BosunCallStats {
-            let stats = self.stats.borrow_mut();
-
             BosunCallStats {
-                metadata_count: *stats.get("metadata").unwrap_or(&0),
-                datum_count: *stats.get("datum").unwrap_or(&0),
-                set_silence_count: *stats.get("set_silence").unwrap_or(&0),
+                metadata_count: self.calls(Call::Metadata),
+                datum_count: self.calls(Call::Datum),
+                set_silence_count: self.calls(Call::SetSilence),
             }
         }
     }
@@ -445,4 +1705,331 @@ mod tests {
 
         assert_that(&json).is_ok().is_equal_to(&expected);
     }
+
+    #[test]
+    fn deserialize_silences() {
+        let json = r#"{
+  "2d7e3a2c0e9d8d1d4b5b0c9f2a41a6d0b6b3b0c1": {
+    "Start": "2018-11-28T10:00:00Z",
+    "End": "2018-11-28T10:15:00Z",
+    "Alert": "",
+    "Tags": { "host": "webserver-i-1234567890abcdef0*" },
+    "TagString": "host=webserver-i-1234567890abcdef0*",
+    "Forget": true,
+    "User": "kevin.lambda",
+    "Message": "Host has been terminated by ASG."
+  },
+  "8f4c8d7b2d0f5d2e1b6f9a3c4e5d6f7a8b9c0d1e": {
+    "Start": "2018-11-28T10:00:00.123456789+01:00",
+    "End": "2018-11-29T10:00:00.123456789+01:00",
+    "Alert": "haproxy.down",
+    "Tags": null,
+    "TagString": "",
+    "Forget": false,
+    "User": "ops",
+    "Message": "Maintenance"
+  }
+}"#;
+
+        let silences: Result<Silences, _> = serde_json::from_str(json);
+
+        assert_that(&silences).is_ok().has_length(2);
+        let silences = silences.unwrap();
+        let silence = &silences["2d7e3a2c0e9d8d1d4b5b0c9f2a41a6d0b6b3b0c1"];
+        assert_that(&silence.has_tag("host", "webserver-i-1234567890abcdef0*")).is_true();
+        assert_that(&silence.forget).is_true();
+        let silence = &silences["8f4c8d7b2d0f5d2e1b6f9a3c4e5d6f7a8b9c0d1e"];
+        assert_that(&silence.has_tag("host", "webserver-i-1234567890abcdef0*")).is_false();
+        assert_that(&silence.alert.as_str()).is_equal_to("haproxy.down");
+    }
+
+    #[test]
+    fn silence_is_active_at() {
+        let json = r#"{
+    "Start": "2018-11-28T10:00:00Z",
+    "End": "2018-11-28T10:15:00Z",
+    "Alert": "",
+    "Tags": { "host": "webserver-i-1234567890abcdef0*" },
+    "TagString": "host=webserver-i-1234567890abcdef0*",
+    "Forget": true,
+    "User": "kevin.lambda",
+    "Message": "Host has been terminated by ASG."
+}"#;
+        let silence: SilenceInfo = serde_json::from_str(json).unwrap();
+
+        let during = "2018-11-28T10:05:00Z".parse::<DateTime<Utc>>().unwrap();
+        let after = "2018-11-28T10:15:00Z".parse::<DateTime<Utc>>().unwrap();
+
+        assert_that(&silence.is_active_at(during)).is_true();
+        assert_that(&silence.is_active_at(after)).is_false();
+    }
+
+    #[test]
+    fn silence_to_json() {
+        let start = "2018-11-28T10:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let end = "2018-11-28T10:15:00Z".parse::<DateTime<Utc>>().unwrap();
+        let silence = Silence::builder()
+            .host("webserver-i-1234567890abcdef0*")
+            .tag("service", "nginx")
+            .alert("haproxy.down")
+            .start(start)
+            .end(end)
+            .forget(true)
+            .user("aws-watchtower")
+            .message("Host has been terminated by ASG.")
+            .build()
+            .expect("failed to build silence");
+
+        let json: HashMap<String, String> = serde_json::from_str(&silence.to_json().unwrap()).unwrap();
+
+        assert_that(&json).has_length(8);
+        assert_that(&json["start"].as_str()).is_equal_to("2018/11/28-10:00:00");
+        assert_that(&json["end"].as_str()).is_equal_to("2018/11/28-10:15:00");
+        assert_that(&json["alert"].as_str()).is_equal_to("haproxy.down");
+        assert_that(&json["tags"].as_str()).is_equal_to("host=webserver-i-1234567890abcdef0*,service=nginx");
+        assert_that(&json["forget"].as_str()).is_equal_to("true");
+        assert_that(&json["user"].as_str()).is_equal_to("aws-watchtower");
+        assert_that(&json["confirm"].as_str()).is_equal_to("true");
+    }
+
+    #[test]
+    fn silence_to_json_with_duration_unconfirmed() {
+        let silence = Silence::builder()
+            .host("webserver-*")
+            .duration("15m")
+            .confirm(false)
+            .user("aws-watchtower")
+            .build()
+            .expect("failed to build silence");
+
+        let json: HashMap<String, String> = serde_json::from_str(&silence.to_json().unwrap()).unwrap();
+
+        assert_that(&json["duration"].as_str()).is_equal_to("15m");
+        assert_that(&json["forget"].as_str()).is_equal_to("false");
+        assert_that(&json.contains_key("start")).is_false();
+        assert_that(&json.contains_key("confirm")).is_false();
+    }
+
+    #[test]
+    fn silence_builder_validates() {
+        let no_match = Silence::builder().duration("15m").user("aws-watchtower").build();
+        assert_that(&no_match).is_err();
+
+        let no_end = Silence::builder().host("webserver-*").user("aws-watchtower").build();
+        assert_that(&no_end).is_err();
+
+        let end = "2018-11-28T10:15:00Z".parse::<DateTime<Utc>>().unwrap();
+        let end_and_duration = Silence::builder()
+            .host("webserver-*")
+            .end(end)
+            .duration("15m")
+            .user("aws-watchtower")
+            .build();
+        assert_that(&end_and_duration).is_err();
+
+        let end_before_start = Silence::builder()
+            .host("webserver-*")
+            .start(end)
+            .end(end - chrono::Duration::minutes(1))
+            .user("aws-watchtower")
+            .build();
+        assert_that(&end_before_start).is_err();
+
+        let no_user = Silence::builder().alert("haproxy.down").duration("15m").build();
+        assert_that(&no_user).is_err();
+    }
+
+    #[test]
+    fn buffered_emitter_flushes_at_capacity_and_on_drop() {
+        let bosun = testing::BosunMockClient::default();
+        let tags = Tags::new();
+        {
+            let mut emitter = BufferedEmitter::with_capacity(&bosun, 2);
+            for i in 0..5 {
+                let value = i.to_string();
+                let datum = Datum::new("a_test_metric", 1_545_918_681_110 + i, &value, &tags);
+                emitter.emit(&datum).expect("failed to emit datum");
+            }
+            assert_that(&emitter.len()).is_equal_to(1);
+        }
+
+        let stats = bosun.stats.borrow();
+        assert_that(&stats["emit_data"]).is_equal_to(3);
+        assert_that(&stats["datum"]).is_equal_to(5);
+    }
+
+    #[test]
+    fn buffered_emitter_explicit_flush() {
+        let bosun = testing::BosunMockClient::default();
+        let tags = Tags::new();
+        let mut emitter = BufferedEmitter::new(&bosun);
+        let datum = Datum::new("a_test_metric", 1_545_918_681_110, "42", &tags);
+        emitter.emit(&datum).expect("failed to emit datum");
+
+        let res = emitter.flush();
+
+        assert_that(&res).is_ok();
+        assert_that(&emitter.is_empty()).is_true();
+        assert_that(&bosun.to_stats().datum_count).is_equal_to(1);
+    }
+
+    #[test]
+    fn retry_policy_backoff() {
+        let policy = RetryPolicy::default();
+
+        assert_that(&policy.backoff(0)).is_equal_to(Duration::from_millis(100));
+        assert_that(&policy.backoff(2)).is_equal_to(Duration::from_millis(400));
+        assert_that(&policy.backoff(10)).is_equal_to(Duration::from_secs(2));
+        assert_that(&policy.backoff(40)).is_equal_to(Duration::from_secs(2));
+    }
+
+    #[test]
+    fn retry_only_idempotent_requests() {
+        assert_that(&is_idempotent(&reqwest::Method::GET, "/api/silence/get")).is_true();
+        assert_that(&is_idempotent(&reqwest::Method::PUT, "/api/annotation")).is_true();
+        assert_that(&is_idempotent(&reqwest::Method::POST, "/api/put")).is_true();
+        assert_that(&is_idempotent(&reqwest::Method::POST, "/api/silence/clear?id=1234")).is_true();
+        assert_that(&is_idempotent(&reqwest::Method::POST, "/api/annotation")).is_false();
+        assert_that(&is_idempotent(&reqwest::Method::POST, "/api/silence/set")).is_false();
+    }
+
+    #[test]
+    fn spool_unavailable_bosun() {
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let path = dir.path().join("spool");
+        // Nothing listens on port 1, so connecting fails immediately.
+        let mut bosun = BosunClient::new("localhost:1", 1);
+        bosun.set_retry_policy(RetryPolicy::none());
+        bosun.set_spool(Spool::new(&path));
+        let tags = Tags::new();
+        let datum = Datum::new("a_test_metric", 1_545_918_681_110, "42", &tags);
+
+        let res = bosun.emit_datum(&datum);
+        let replayed = bosun.replay_spool();
+
+        assert_that(&res).is_ok();
+        assert_that(&replayed).is_ok().is_equal_to(0);
+        assert_that(&bosun.spool.as_ref().unwrap().take()).is_ok().has_length(1);
+    }
+
+    #[test]
+    fn spool_silence_with_original_window() {
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let path = dir.path().join("spool");
+        let mut bosun = BosunClient::new("localhost:1", 1);
+        bosun.set_retry_policy(RetryPolicy::none());
+        bosun.set_spool(Spool::new(&path));
+        let silence = Silence::builder()
+            .alert("test.lukas")
+            .duration("1h")
+            .user("lukas")
+            .message("Test")
+            .build()
+            .expect("failed to build silence");
+
+        let before = Utc::now().format(SILENCE_TIME_FORMAT).to_string();
+        let res = bosun.set_silence(&silence);
+        let after = Utc::now().format(SILENCE_TIME_FORMAT).to_string();
+
+        assert_that(&res).is_ok();
+        let requests = bosun.spool.as_ref().unwrap().take().expect("failed to read spool");
+        assert_that(&requests).has_length(1);
+        let body: HashMap<String, String> =
+            serde_json::from_str(&requests[0].body).expect("failed to parse spooled silence");
+        let start = body.get("start").expect("spooled silence has no start");
+        assert_that(&(before.as_str() <= start.as_str() && start.as_str() <= after.as_str())).is_true();
+        assert_that(&body.get("duration")).is_equal_to(Some(&"1h".to_string()));
+    }
+
+    #[test]
+    fn mock_records_and_fails_as_scripted() {
+        let bosun = testing::BosunMockClient::default().fail_nth(
+            testing::Call::Datum,
+            2,
+            BosunError::ReceiveError("500 Internal Server Error".to_string()),
+        );
+        let mut tags = Tags::new();
+        tags.insert("asg".to_string(), "webserver".to_string());
+
+        let first = bosun.emit_datum(&Datum::new("a_test_metric", 1_545_918_681_110, "1", &tags));
+        let second = bosun.emit_datum(&Datum::new("a_test_metric", 1_545_918_681_111, "2", &tags));
+        let third = bosun.emit_datum(&Datum::new("a_test_metric", 1_545_918_681_112, "3", &tags));
+
+        assert_that(&first).is_ok();
+        assert_that(&second).is_err();
+        assert_that(&third).is_ok();
+        assert_that(&bosun.calls(testing::Call::Datum)).is_equal_to(3);
+        assert_that(&bosun.data_for("a_test_metric")).has_length(2);
+        assert_that(&bosun.datum_with_tag("a_test_metric", "asg", "webserver"))
+            .is_some()
+            .map(|x| &x.value)
+            .is_equal_to("3".to_string());
+    }
+
+    #[test]
+    fn update_annotation_requires_id() {
+        let bosun = BosunClient::new("localhost:1", 1);
+        let annotation = Annotation::new("aws-scaletower", "burst_balance", "Terminated instance");
+
+        let res = bosun.update_annotation(&annotation);
+
+        assert_that(&res).is_err_containing(BosunError::InvalidAnnotation("id is not set"));
+    }
+
+    #[test]
+    fn alert_status_is_worst_status_of_matching_incidents() {
+        let json = r#"[
+  {"Id":1,"Start":1602842400,"AlertName":"os.cpu.high","Tags":{"host":"webserver-1","asg":"webserver"},
+   "CurrentStatus":"warning","WorstStatus":"warning"},
+  {"Id":2,"Start":1602842400,"AlertName":"os.disk.full","Tags":{"host":"webserver-2","asg":"webserver"},
+   "CurrentStatus":"critical","WorstStatus":"critical"}
+]"#;
+        let incidents: Vec<Incident> = serde_json::from_str(json).unwrap();
+        let bosun = testing::BosunMockClient::with_incidents(incidents);
+        let mut host = Tags::new();
+        host.insert("host".to_string(), "webserver-1".to_string());
+        let mut asg = Tags::new();
+        asg.insert("asg".to_string(), "webserver".to_string());
+        let mut other = Tags::new();
+        other.insert("asg".to_string(), "database".to_string());
+
+        assert_that(&bosun.alert_status(&host))
+            .is_ok()
+            .is_equal_to(AlertStatus::Warning);
+        assert_that(&bosun.alert_status(&asg))
+            .is_ok()
+            .is_equal_to(AlertStatus::Critical);
+        assert_that(&bosun.alert_status(&other))
+            .is_ok()
+            .is_equal_to(AlertStatus::Normal);
+    }
+
+    #[test]
+    fn ca_bundle_with_multiple_certificates() {
+        let bundle = b"-----BEGIN CERTIFICATE-----\nMIIB\n-----END CERTIFICATE-----\n\
+-----BEGIN CERTIFICATE-----\nMIIC\n-----END CERTIFICATE-----\n";
+
+        let connection = ConnectionConfig::default().add_ca_bundle(bundle);
+
+        assert_that(&connection.ca_certificates).has_length(2);
+        assert_that(&connection.ca_certificates[1].as_slice())
+            .is_equal_to(&b"-----BEGIN CERTIFICATE-----\nMIIC\n-----END CERTIFICATE-----"[..]);
+        let mut bosun = BosunClient::new("bosun.internal:8070", 1);
+        bosun.connection = connection;
+        assert_that(&bosun.uri_for("/api/put").as_str()).is_equal_to("https://bosun.internal:8070/api/put");
+    }
+
+    #[test]
+    fn set_connection_rejects_invalid_options() {
+        let mut bosun = BosunClient::new("localhost:8070", 1);
+        let connection = ConnectionConfig {
+            ca_certificates: vec![b"not a certificate".to_vec()],
+            ..Default::default()
+        };
+
+        let res = bosun.set_connection(connection);
+
+        assert_that(&res).is_err();
+        assert_that(&bosun.uri_for("/api/put").as_str()).is_equal_to("http://localhost:8070/api/put");
+    }
 }
@@ -0,0 +1,133 @@
+//! A registry of the metrics a function emits.
+//!
+//! Each function declares its metrics once. The registry then provides the metadata to send to Bosun and creates
+//! data points, refusing metric names that have not been registered.
+
+use std::collections::BTreeMap;
+
+use crate::{now_in_ms, BosunError, Datum, Metadata, Rate, Tags, Unit};
+
+/// Declaration of a single metric.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Metric {
+    pub name: &'static str,
+    pub rate: Rate,
+    pub unit: Unit,
+    pub description: &'static str,
+}
+
+impl Metric {
+    pub fn metadata(&self) -> Metadata<'static> {
+        Metadata::new(self.name, self.rate, self.unit, self.description)
+    }
+}
+
+/// Registered metrics by name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MetricRegistry {
+    metrics: BTreeMap<&'static str, Metric>,
+}
+
+impl MetricRegistry {
+    pub fn new() -> MetricRegistry {
+        MetricRegistry::default()
+    }
+
+    /// Registers a metric; registering the same name again replaces the previous declaration.
+    pub fn register(mut self, name: &'static str, rate: Rate, unit: Unit, description: &'static str) -> Self {
+        let metric = Metric {
+            name,
+            rate,
+            unit,
+            description,
+        };
+        self.metrics.insert(name, metric);
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Metric> {
+        self.metrics.get(name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.metrics.contains_key(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.metrics.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.metrics.is_empty()
+    }
+
+    /// Metadata of all registered metrics ordered by metric name.
+    pub fn metadata(&self) -> Vec<Metadata<'static>> {
+        self.metrics.values().map(Metric::metadata).collect()
+    }
+
+    /// Creates a data point for a registered metric with a specified timestamp in ms.
+    pub fn datum<'a>(
+        &self,
+        name: &'a str,
+        timestamp: i64,
+        value: &'a str,
+        tags: &'a Tags,
+    ) -> Result<Datum<'a>, BosunError> {
+        if !self.contains(name) {
+            return Err(BosunError::UnknownMetric(name.to_string()));
+        }
+
+        Ok(Datum::new(name, timestamp, value, tags))
+    }
+
+    /// Creates a data point for a registered metric with timestamp _now_.
+    pub fn datum_now<'a>(&self, name: &'a str, value: &'a str, tags: &'a Tags) -> Result<Datum<'a>, BosunError> {
+        self.datum(name, now_in_ms(), value, tags)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use spectral::prelude::*;
+
+    fn registry() -> MetricRegistry {
+        MetricRegistry::new()
+            .register("b.metric", Rate::Gauge, Unit::Days, "A gauge")
+            .register("a.metric", Rate::Rate, Unit::Change, "A rate")
+    }
+
+    #[test]
+    fn metadata_ordered_by_name() {
+        let registry = registry();
+
+        let metadata = registry.metadata();
+
+        assert_that(&metadata).has_length(2);
+        assert_that(&metadata[0].metric).is_equal_to("a.metric");
+        assert_that(&metadata[0].rate).is_equal_to(Rate::Rate);
+        assert_that(&metadata[1].unit).is_equal_to(Unit::Days);
+    }
+
+    #[test]
+    fn datum_for_registered_metric() {
+        let registry = registry();
+        let tags = Tags::new();
+
+        let datum = registry.datum("a.metric", 1_545_918_681_110, "1", &tags);
+
+        assert_that(&datum).is_ok().map(|x| &x.metric).is_equal_to("a.metric");
+    }
+
+    #[test]
+    fn datum_for_unregistered_metric() {
+        let registry = registry();
+        let tags = Tags::new();
+
+        let datum = registry.datum_now("c.metric", "1", &tags);
+
+        assert_that(&datum).is_err();
+    }
+}
@@ -0,0 +1,338 @@
+//! Metric sinks decouple emitting metric data from the monitoring backend.
+//!
+//! Every sink accepts the same OpenTSDB-shaped `Metadata` and `Datum` that Bosun uses, so a function can switch its
+//! monitoring backend without changing how it creates metrics.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+    sync::Mutex,
+    time::Duration,
+};
+
+use log::{debug, info};
+use reqwest::StatusCode;
+
+use crate::{
+    Bosun, BosunClient, BosunError, BosunResult, Datum, InternalDatum, Metadata, Rate, Tags, DEFAULT_MAX_BATCH_SIZE,
+};
+
+/// Receives metric metadata and metric data.
+pub trait MetricSink {
+    /// Emits metric metadata; sinks without a notion of metadata may ignore it.
+    fn emit_metadata(&self, metadata: &Metadata) -> BosunResult;
+    /// Emits metric data.
+    fn emit_data(&self, data: &[Datum]) -> BosunResult;
+}
+
+impl MetricSink for BosunClient {
+    fn emit_metadata(&self, metadata: &Metadata) -> BosunResult {
+        Bosun::emit_metadata(self, metadata)
+    }
+
+    fn emit_data(&self, data: &[Datum]) -> BosunResult {
+        Bosun::emit_data(self, data)
+    }
+}
+
+/// Sends metric data directly to OpenTSDB's `/api/put`; OpenTSDB does not support metadata, so it is ignored.
+#[derive(Debug)]
+pub struct OpenTsdbSink {
+    /// `<HOSTNAME|IP ADDR>:<PORT>` or URL
+    pub host: String,
+    pub default_tags: Tags,
+    /// Maximum number of data points sent in a single request
+    pub max_batch_size: usize,
+    client: reqwest::Client,
+}
+
+impl OpenTsdbSink {
+    pub fn new(host: &str, timeout: u64, default_tags: Tags) -> Result<OpenTsdbSink, BosunError> {
+        Ok(OpenTsdbSink {
+            host: host.to_string(),
+            default_tags,
+            max_batch_size: DEFAULT_MAX_BATCH_SIZE,
+            client: http_client(timeout)?,
+        })
+    }
+}
+
+impl MetricSink for OpenTsdbSink {
+    fn emit_metadata(&self, metadata: &Metadata) -> BosunResult {
+        debug!("Ignoring metadata for metric '{}' for OpenTSDB.", metadata.metric);
+        Ok(())
+    }
+
+    fn emit_data(&self, data: &[Datum]) -> BosunResult {
+        let uri = uri_for(&self.host, "/api/put");
+        for chunk in data.chunks(self.max_batch_size.max(1)) {
+            let internal_data: Vec<InternalDatum> = chunk
+                .iter()
+                .map(|datum| {
+                    let mut internal_datum: InternalDatum = datum.into();
+                    internal_datum.add_tags(&self.default_tags);
+                    internal_datum
+                })
+                .collect();
+            let encoded = serde_json::to_string(&internal_data).map_err(|_| BosunError::JsonParseError)?;
+
+            let res = self
+                .client
+                .post(&uri)
+                .header("Content-Type", "application/json; charset=utf-8")
+                .body(encoded)
+                .send();
+            let res = check_response(res, StatusCode::NO_CONTENT);
+            info!(
+                "Sent {} data to OpenTSDB at '{:?}' with result: '{:?}'.",
+                internal_data.len(),
+                &self.host,
+                res
+            );
+            res?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Pushes metric data in Prometheus' text exposition format to a Pushgateway.
+///
+/// Metric and tag names are converted to valid Prometheus names, e.g., `aws.ec2.asg.scaling` becomes
+/// `aws_ec2_asg_scaling`. The Pushgateway does not accept timestamps, so only the value with the latest timestamp of
+/// each time series in a batch is pushed. Metadata is remembered and pushed as `HELP` and `TYPE` comments.
+#[derive(Debug)]
+pub struct PushgatewaySink {
+    /// URL of the Pushgateway, e.g., `http://pushgateway:9091`
+    pub url: String,
+    /// Job name used as grouping key
+    pub job: String,
+    pub default_tags: Tags,
+    metadata: Mutex<HashMap<String, (Rate, String)>>,
+    client: reqwest::Client,
+}
+
+impl PushgatewaySink {
+    pub fn new(url: &str, job: &str, timeout: u64, default_tags: Tags) -> Result<PushgatewaySink, BosunError> {
+        Ok(PushgatewaySink {
+            url: url.to_string(),
+            job: job.to_string(),
+            default_tags,
+            metadata: Mutex::new(HashMap::new()),
+            client: http_client(timeout)?,
+        })
+    }
+
+    /// Returns the URL to push this job's metrics to; the job name is percent-encoded as a path segment.
+    pub fn job_url(&self) -> Result<reqwest::Url, BosunError> {
+        let mut url = reqwest::Url::parse(&self.url)
+            .map_err(|e| BosunError::EmitError(format!("invalid Pushgateway URL '{}' because {}", self.url, e)))?;
+        url.path_segments_mut()
+            .map_err(|_| BosunError::EmitError(format!("invalid Pushgateway URL '{}'", self.url)))?
+            .pop_if_empty()
+            .extend(&["metrics", "job", &self.job]);
+
+        Ok(url)
+    }
+
+    /// Renders data in Prometheus' text exposition format.
+    pub fn to_text(&self, data: &[Datum]) -> String {
+        // metric name -> (sorted labels -> (timestamp, value)); BTreeMaps keep the output stable and group each metric
+        // family.
+        let mut families: BTreeMap<String, BTreeMap<String, (i64, &str)>> = BTreeMap::new();
+        for datum in data {
+            let mut labels: BTreeMap<String, &str> = BTreeMap::new();
+            for (k, v) in self.default_tags.iter().chain(datum.tags.iter()) {
+                labels.insert(prometheus_name(k), v);
+            }
+            let labels: Vec<_> = labels
+                .iter()
+                .map(|(k, v)| format!("{}=\"{}\"", k, escape_label_value(v)))
+                .collect();
+            let sample = families
+                .entry(prometheus_name(datum.metric))
+                .or_default()
+                .entry(labels.join(","))
+                .or_insert((datum.timestamp, datum.value));
+            if datum.timestamp >= sample.0 {
+                *sample = (datum.timestamp, datum.value);
+            }
+        }
+
+        let metadata = self
+            .metadata
+            .lock()
+            .expect("Could not retrieve Mutex lock for metadata");
+        let mut text = String::new();
+        for (name, samples) in families {
+            if let Some((rate, description)) = metadata.get(&name) {
+                let _ = writeln!(
+                    text,
+                    "# HELP {} {}",
+                    name,
+                    description.replace('\\', "\\\\").replace('\n', "\\n")
+                );
+                let _ = writeln!(text, "# TYPE {} {}", name, prometheus_type(*rate));
+            }
+            for (labels, (_, value)) in samples {
+                let _ = writeln!(text, "{}{{{}}} {}", name, labels, value);
+            }
+        }
+
+        text
+    }
+}
+
+impl MetricSink for PushgatewaySink {
+    fn emit_metadata(&self, metadata: &Metadata) -> BosunResult {
+        let mut known = self
+            .metadata
+            .lock()
+            .expect("Could not retrieve Mutex lock for metadata");
+        known.insert(
+            prometheus_name(metadata.metric),
+            (metadata.rate, metadata.description.to_string()),
+        );
+
+        Ok(())
+    }
+
+    fn emit_data(&self, data: &[Datum]) -> BosunResult {
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        let uri = self.job_url()?;
+        let text = self.to_text(data);
+        let res = self
+            .client
+            .post(uri)
+            .header("Content-Type", "text/plain; version=0.0.4")
+            .body(text)
+            .send();
+        let res = check_response(res, StatusCode::OK).or_else(|e| match e {
+            // Older Pushgateway versions answer with 202 Accepted.
+            BosunError::ReceiveError(ref status) if status.starts_with("202") => Ok(()),
+            _ => Err(e),
+        });
+        info!(
+            "Pushed {} data to Pushgateway at '{:?}' with result: '{:?}'.",
+            data.len(),
+            &self.url,
+            res
+        );
+
+        res
+    }
+}
+
+fn uri_for(host: &str, path: &str) -> String {
+    if host.starts_with("http") {
+        format!("{}{}", host, path)
+    } else {
+        format!("http://{}{}", host, path)
+    }
+}
+
+fn http_client(timeout: u64) -> Result<reqwest::Client, BosunError> {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(timeout))
+        .build()
+        .map_err(|e| BosunError::EmitError(format!("failed to build http client because {}", e)))
+}
+
+fn check_response(res: Result<reqwest::Response, reqwest::Error>, expected: StatusCode) -> BosunResult {
+    match res {
+        Ok(ref response) if response.status() == expected => Ok(()),
+        Ok(response) => Err(BosunError::ReceiveError(format!("{}", response.status()))),
+        Err(err) => Err(BosunError::EmitError(format!("{}", err))),
+    }
+}
+
+/// Replaces all characters not allowed in Prometheus metric and label names by `_`.
+fn prometheus_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == ':' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", name)
+    } else {
+        name
+    }
+}
+
+fn escape_label_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Maps Bosun's rate types to Prometheus metric types.
+fn prometheus_type(rate: Rate) -> &'static str {
+    match rate {
+        Rate::Counter => "counter",
+        Rate::Gauge | Rate::Rate => "gauge",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::Unit;
+
+    use spectral::prelude::*;
+
+    #[test]
+    fn prometheus_names() {
+        assert_that(&prometheus_name("aws.ec2.asg.scaling")).is_equal_to("aws_ec2_asg_scaling".to_string());
+        assert_that(&prometheus_name("function-name")).is_equal_to("function_name".to_string());
+        assert_that(&prometheus_name("1st")).is_equal_to("_1st".to_string());
+    }
+
+    #[test]
+    fn pushgateway_to_text() {
+        let mut default_tags = Tags::new();
+        default_tags.insert("host".to_string(), "lambda".to_string());
+        let sink = PushgatewaySink::new("http://localhost:9091", "test", 5, default_tags).unwrap();
+        let metadata = Metadata::new(
+            "lambda.invocation.count",
+            Rate::Counter,
+            Unit::Invocations,
+            "Invocation counter",
+        );
+        MetricSink::emit_metadata(&sink, &metadata).unwrap();
+
+        let mut tags = Tags::new();
+        tags.insert("user_name".to_string(), "a \"quoted\" user".to_string());
+        let data = vec![
+            Datum::new("lambda.invocation.count", 1_545_918_681_110, "1", &tags),
+            Datum::new("lambda.invocation.count", 1_545_918_681_112, "3", &tags),
+            Datum::new("lambda.invocation.count", 1_545_918_681_111, "2", &tags),
+            Datum::new("aws.ec2.asg.scaling", 1_545_918_681_110, "-1", &tags),
+        ];
+
+        let text = sink.to_text(&data);
+
+        let expected = r#"aws_ec2_asg_scaling{host="lambda",user_name="a \"quoted\" user"} -1
+# HELP lambda_invocation_count Invocation counter
+# TYPE lambda_invocation_count counter
+lambda_invocation_count{host="lambda",user_name="a \"quoted\" user"} 3
+"#;
+        assert_that(&text.as_str()).is_equal_to(expected);
+    }
+
+    #[test]
+    fn pushgateway_job_url_encodes_job() {
+        let sink = PushgatewaySink::new("http://localhost:9091/", "aws watchtower/prod", 5, Tags::new()).unwrap();
+
+        let url = sink.job_url().unwrap();
+
+        assert_that(&url.as_str()).is_equal_to("http://localhost:9091/metrics/job/aws%20watchtower%2Fprod");
+    }
+}
@@ -0,0 +1,167 @@
+//! A file based spool for Bosun API requests that could not be delivered.
+//!
+//! AWS Lambda keeps `/tmp` as long as a function instance stays warm, so requests spooled during one invocation can
+//! be replayed at the beginning of the next one.
+
+use std::{
+    cell::Cell,
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, ErrorKind, Write},
+    path::PathBuf,
+};
+
+use log::{debug, warn};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::BosunError;
+
+/// Default maximum number of requests kept in a spool.
+pub const DEFAULT_MAX_SPOOL_ENTRIES: usize = 10_000;
+
+/// A Bosun API request that failed to be delivered.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SpooledRequest {
+    pub path: String,
+    pub body: String,
+    /// Expected HTTP status code
+    pub expected: u16,
+}
+
+/// Spools requests as JSON lines to a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Spool {
+    pub path: PathBuf,
+    /// Requests beyond this number are dropped to keep the spool from filling up the disk.
+    pub max_entries: usize,
+    /// Number of spooled requests; counted from the file on the first push only
+    entries: Cell<Option<usize>>,
+}
+
+impl Spool {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Spool {
+        Spool {
+            path: path.into(),
+            max_entries: DEFAULT_MAX_SPOOL_ENTRIES,
+            entries: Cell::new(None),
+        }
+    }
+
+    /// Appends `request` to the spool.
+    pub fn push(&self, request: &SpooledRequest) -> Result<(), BosunError> {
+        let entries = match self.entries.get() {
+            Some(entries) => entries,
+            None => self.len()?,
+        };
+        self.entries.set(Some(entries));
+        if entries >= self.max_entries {
+            warn!(
+                "Dropping request to '{}', because spool '{:?}' is full.",
+                request.path, self.path
+            );
+            return Ok(());
+        }
+
+        let line = serde_json::to_string(request).map_err(|_| BosunError::JsonParseError)?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| spool_error(&self.path, e))?;
+        writeln!(file, "{}", line).map_err(|e| spool_error(&self.path, e))?;
+        self.entries.set(Some(entries + 1));
+        debug!("Spooled request to '{}' in '{:?}'.", request.path, self.path);
+
+        Ok(())
+    }
+
+    /// Number of spooled requests.
+    pub fn len(&self) -> Result<usize, BosunError> {
+        Ok(self.read()?.len())
+    }
+
+    pub fn is_empty(&self) -> Result<bool, BosunError> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Removes and returns all spooled requests; lines that cannot be parsed are dropped.
+    pub fn take(&self) -> Result<Vec<SpooledRequest>, BosunError> {
+        let requests = self.read()?;
+        match fs::remove_file(&self.path) {
+            Ok(_) => {}
+            Err(ref e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => return Err(spool_error(&self.path, e)),
+        }
+        self.entries.set(Some(0));
+
+        Ok(requests)
+    }
+
+    fn read(&self) -> Result<Vec<SpooledRequest>, BosunError> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(ref e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(spool_error(&self.path, e)),
+        };
+
+        let mut requests = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(|e| spool_error(&self.path, e))?;
+            match serde_json::from_str(&line) {
+                Ok(request) => requests.push(request),
+                Err(e) => warn!("Dropping unreadable spool entry '{}' because {}.", line, e),
+            }
+        }
+
+        Ok(requests)
+    }
+}
+
+fn spool_error(path: &PathBuf, err: std::io::Error) -> BosunError {
+    BosunError::SpoolError(format!("{:?}: {}", path, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use spectral::prelude::*;
+
+    #[test]
+    fn push_and_take() {
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let spool = Spool::new(dir.path().join("spool"));
+        let request = SpooledRequest {
+            path: "/api/put".to_string(),
+            body: r#"[{"metric":"a_test_metric","timestamp":1545918681110,"value":"42","tags":{}}]"#.to_string(),
+            expected: 204,
+        };
+
+        spool.push(&request).expect("failed to spool request");
+        spool.push(&request).expect("failed to spool request");
+        let requests = spool.take().expect("failed to take requests");
+
+        assert_that(&requests).has_length(2);
+        assert_that(&requests[0]).is_equal_to(&request);
+        assert_that(&spool.is_empty()).is_ok().is_true();
+    }
+
+    #[test]
+    fn drop_requests_beyond_max_entries() {
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let mut spool = Spool::new(dir.path().join("spool"));
+        spool.max_entries = 1;
+        let request = SpooledRequest {
+            path: "/api/put".to_string(),
+            body: "[]".to_string(),
+            expected: 204,
+        };
+
+        spool.push(&request).expect("failed to spool request");
+        spool.push(&request).expect("failed to spool request");
+
+        assert_that(&spool.len()).is_ok().is_equal_to(1);
+        assert_that(&spool.take()).is_ok().has_length(1);
+        spool.push(&request).expect("failed to spool request");
+        assert_that(&spool.len()).is_ok().is_equal_to(1);
+    }
+}
@@ -6,7 +6,7 @@ use failure::Error;
 use serde_derive::{Deserialize, Serialize};
 
 use aws::{kms, AwsClientConfig};
-use lambda::config::{BosunConfig, EncryptedConfig};
+use lambda::config::{BosunConfig, EncryptedConfig, MetricSinkConfig};
 
 #[derive(Config, PartialEq, Deserialize, Serialize, Debug)]
 pub struct EncryptedFunctionConfig {
@@ -18,8 +18,11 @@ impl EncryptedConfig<EncryptedFunctionConfig, FunctionConfig> for EncryptedFunct
     fn decrypt(self, aws_client_config: &AwsClientConfig) -> Result<FunctionConfig, Error> {
         let bosun_auth_password = kms::decrypt_base64(aws_client_config, &self.bosun.password)?;
 
+        let bosun_tls = self.bosun.tls.map(|tls| tls.decrypt(aws_client_config)).transpose()?;
+
         let bosun = BosunConfig {
             password: bosun_auth_password,
+            tls: bosun_tls,
             ..self.bosun
         };
 
@@ -48,6 +51,12 @@ impl Default for FunctionConfig {
             password: "bosun".to_string(),
             timeout: Some(5),
             tags: HashMap::new(),
+            retries: None,
+            spool_file: None,
+            sink: MetricSinkConfig::Bosun,
+            connect_timeout: None,
+            proxy: None,
+            tls: None,
         };
 
         let centerdevice_health = CenterDeviceHealthConfig {
@@ -4,7 +4,7 @@ use log::{debug, info};
 use serde_derive::Deserialize;
 
 use aws::AwsClientConfig;
-use bosun::{Bosun, Datum, Tags};
+use bosun::{Bosun, BufferedEmitter, Tags};
 
 use crate::centerdevice::health::{self, HealthCheck, HealthCheckResult, HealthSamples};
 use crate::config::{CenterDeviceHealthConfig, FunctionConfig};
@@ -62,17 +62,23 @@ pub fn centerdevice_health<T: Bosun>(config: &CenterDeviceHealthConfig, bosun: &
 }
 
 fn bosun_send_healthchecks<T: Bosun>(bosun: &T, health_checks: &Vec<HealthCheck>) -> Result<(), Error> {
+    let mut emitter = BufferedEmitter::new(bosun);
     for hc in health_checks {
         match &hc.result {
-            HealthCheckResult::Ok(ref samples) => bosun_emit_health_samples(bosun, &hc.service, samples)?,
-            HealthCheckResult::Failed(_) => bosun_emit_check_failure(bosun, &hc.service)?,
+            HealthCheckResult::Ok(ref samples) => bosun_emit_health_samples(&mut emitter, &hc.service, samples)?,
+            HealthCheckResult::Failed(_) => bosun_emit_check_failure(&mut emitter, &hc.service)?,
         }
     }
+    emitter.flush()?;
 
     Ok(())
 }
 
-fn bosun_emit_health_samples<T: Bosun>(bosun: &T, service: &str, samples: &HealthSamples) -> Result<(), Error> {
+fn bosun_emit_health_samples<T: Bosun>(
+    emitter: &mut BufferedEmitter<T>,
+    service: &str,
+    samples: &HealthSamples,
+) -> Result<(), Error> {
     for (resource, sample) in samples {
         let mut tags = Tags::new();
         tags.insert("service".to_string(), service.to_string());
@@ -81,24 +87,24 @@ fn bosun_emit_health_samples<T: Bosun>(bosun: &T, service: &str, samples: &Healt
         let value = if sample.healthy { "1" } else { "0" };
 
         let datum = if let Some(timestamp) = sample.time_stamp {
-            Datum::new(metrics::CENTERDEVICE_HEALTH, timestamp, &value, &tags)
+            metrics::METRICS.datum(metrics::CENTERDEVICE_HEALTH, timestamp, value, &tags)?
         } else {
-            Datum::now(metrics::CENTERDEVICE_HEALTH, &value, &tags)
+            metrics::METRICS.datum_now(metrics::CENTERDEVICE_HEALTH, value, &tags)?
         };
 
-        bosun.emit_datum(&datum)?;
+        emitter.emit(&datum)?;
     }
 
     Ok(())
 }
 
-fn bosun_emit_check_failure<T: Bosun>(bosun: &T, service: &str) -> Result<(), Error> {
+fn bosun_emit_check_failure<T: Bosun>(emitter: &mut BufferedEmitter<T>, service: &str) -> Result<(), Error> {
     let mut tags = Tags::new();
     tags.insert("service".to_string(), service.to_string());
     tags.insert("resource".to_string(), "global".to_string());
     let value = "-1".to_string();
-    let datum = Datum::now(metrics::CENTERDEVICE_HEALTH, &value, &tags);
-    bosun.emit_datum(&datum)?;
+    let datum = metrics::METRICS.datum_now(metrics::CENTERDEVICE_HEALTH, &value, &tags)?;
+    emitter.emit(&datum)?;
 
     Ok(())
 }
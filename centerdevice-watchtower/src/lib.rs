@@ -48,7 +48,8 @@ fn run(json: Value, ctx: &Context) -> Result<(), Error> {
     }
 
     // Run per each invocation
-    let bosun = lambda::bosun::init(&CONFIG.bosun, ctx).map_err(|e| ctx.new_error(e.to_string().as_str()))?;
+    let bosun = lambda::bosun::init(&CONFIG.bosun, &AWS_CLIENT_CONFIG, ctx)
+        .map_err(|e| ctx.new_error(e.to_string().as_str()))?;
 
     // Only run once per instance of lambda function
     if invocation_counter == 0 {
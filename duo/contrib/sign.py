@@ -33,6 +33,40 @@ def sign(method, host, path, params, skey, ikey):
     # return headers
     return {'Date': now, 'Authorization': 'Basic %s' % base64.b64encode(auth)}
 
+def sign_v5(method, host, path, params, skey, ikey, body="", x_duo_headers=dict()):
+    """
+    Return HTTP Basic Authentication ("Authorization" and "Date") headers using SHA-512 v5 canonicalization.
+    body: request body
+    x_duo_headers: dict of X-Duo-* headers
+    """
+
+    # create canonical string
+    now = "Tue, 21 Aug 2012 17:29:18 -0000"
+    canon = [now, method.upper(), host.lower(), path]
+    args = []
+    for key in sorted(params.keys()):
+        val = params[key]
+        if isinstance(val, unicode):
+            val = val.encode("utf-8")
+        args.append(
+            '%s=%s' % (urllib.quote(key, '~'), urllib.quote(val, '~')))
+    canon.append('&'.join(args))
+    canon.append(hashlib.sha512(body).hexdigest())
+    headers = []
+    for key in sorted(k.lower() for k in x_duo_headers.keys()):
+        headers.extend([key, dict((k.lower(), v) for k, v in x_duo_headers.items())[key]])
+    canon.append(hashlib.sha512('\x00'.join(headers)).hexdigest())
+    canon = '\n'.join(canon)
+    print("'%s'" % canon)
+
+    # sign canonical string
+    sig = hmac.new(skey, canon, hashlib.sha512)
+    auth = '%s:%s' % (ikey, sig.hexdigest())
+    print(auth)
+
+    # return headers
+    return {'Date': now, 'Authorization': 'Basic %s' % base64.b64encode(auth)}
+
 if __name__ == '__main__':
 
     params = dict()
@@ -43,3 +77,7 @@ if __name__ == '__main__':
     result = sign("POST", "api-XXXXXXXX.duosecurity.com", "/admin/v1/users", params, "Zh5eGmUq9zpfQnyUIu5OL9iWoMMv5ZNmk3zLJ4Ep", "DIWJ8X6AEYOR5OMC6TQ1")
 
     print(result)
+
+    result = sign_v5("POST", "api-XXXXXXXX.duosecurity.com", "/admin/v1/users", params, "Zh5eGmUq9zpfQnyUIu5OL9iWoMMv5ZNmk3zLJ4Ep", "DIWJ8X6AEYOR5OMC6TQ1")
+
+    print(result)
@@ -0,0 +1,439 @@
+//! Authentication logs of the Duo Admin API v2, i.e., every authentication attempt with its result and factor.
+//!
+//! cf. https://duo.com/docs/adminapi#authentication-logs
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use log::debug;
+use serde::Deserialize;
+
+use crate::{from_unix_timestamp, Duo, DuoResponse, DuoResult};
+
+/// Maximum number of log events Duo returns per page.
+pub const MAX_AUTH_LOG_LIMIT: usize = 1000;
+
+/// Time window and filters for fetching authentication logs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthLogQuery {
+    pub mintime: DateTime<Utc>,
+    pub maxtime: DateTime<Utc>,
+    pub limit: usize,
+    /// Restricts the logs to these user ids; all users if empty
+    pub users: Vec<String>,
+}
+
+impl AuthLogQuery {
+    /// Creates a query for all events between `mintime` and `maxtime` fetching the maximum number of events per page.
+    pub fn new(mintime: DateTime<Utc>, maxtime: DateTime<Utc>) -> AuthLogQuery {
+        AuthLogQuery {
+            mintime,
+            maxtime,
+            limit: MAX_AUTH_LOG_LIMIT,
+            users: Vec::new(),
+        }
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit.min(MAX_AUTH_LOG_LIMIT);
+        self
+    }
+
+    pub fn user<S: Into<String>>(mut self, user_id: S) -> Self {
+        self.users.push(user_id.into());
+        self
+    }
+
+    /// Returns the query parameters as expected by Duo, i.e., times in milliseconds and comma separated user ids.
+    pub(crate) fn to_params(&self) -> Vec<(&'static str, String)> {
+        let mut params = vec![
+            ("mintime", self.mintime.timestamp_millis().to_string()),
+            ("maxtime", self.maxtime.timestamp_millis().to_string()),
+            ("limit", self.limit.to_string()),
+        ];
+        if !self.users.is_empty() {
+            params.push(("users", self.users.join(",")));
+        }
+
+        params
+    }
+}
+
+/// Opaque cursor pointing to the next page of authentication logs
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct AuthLogOffset(Vec<String>);
+
+impl fmt::Display for AuthLogOffset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0.join(","))
+    }
+}
+
+/// A page of authentication logs
+#[derive(Debug, Deserialize)]
+pub struct AuthLogs {
+    #[serde(rename = "authlogs")]
+    pub auth_logs: Vec<AuthLog>,
+    #[serde(default)]
+    pub metadata: AuthLogMetadata,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct AuthLogMetadata {
+    /// Cursor of the next page; missing on the last page
+    pub next_offset: Option<AuthLogOffset>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuthLog {
+    pub txid: String,
+    #[serde(deserialize_with = "from_unix_timestamp")]
+    pub timestamp: Option<DateTime<Utc>>,
+    pub user: AuthLogUser,
+    pub result: AuthResult,
+    #[serde(default)]
+    pub factor: Option<Factor>,
+    #[serde(default)]
+    pub reason: Option<String>,
+    #[serde(default)]
+    pub event_type: Option<String>,
+    #[serde(default)]
+    pub application: Option<Application>,
+    #[serde(default)]
+    pub access_device: Option<AccessDevice>,
+    #[serde(default)]
+    pub auth_device: Option<AuthDevice>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuthLogUser {
+    /// User id; missing for unknown users
+    pub key: Option<String>,
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthResult {
+    Success,
+    Denied,
+    Fraud,
+    #[serde(other)]
+    Error,
+}
+
+impl fmt::Display for AuthResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match self {
+            AuthResult::Success => "success",
+            AuthResult::Denied => "denied",
+            AuthResult::Fraud => "fraud",
+            AuthResult::Error => "error",
+        };
+        f.write_str(str)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Factor {
+    DuoPush,
+    DuoMobilePasscode,
+    PhoneCall,
+    SmsPasscode,
+    HardwareToken,
+    YubikeyCode,
+    BypassCode,
+    RememberedDevice,
+    TrustedNetwork,
+    U2fToken,
+    #[serde(other)]
+    Other,
+}
+
+impl fmt::Display for Factor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match self {
+            Factor::DuoPush => "duo_push",
+            Factor::DuoMobilePasscode => "duo_mobile_passcode",
+            Factor::PhoneCall => "phone_call",
+            Factor::SmsPasscode => "sms_passcode",
+            Factor::HardwareToken => "hardware_token",
+            Factor::YubikeyCode => "yubikey_code",
+            Factor::BypassCode => "bypass_code",
+            Factor::RememberedDevice => "remembered_device",
+            Factor::TrustedNetwork => "trusted_network",
+            Factor::U2fToken => "u2f_token",
+            Factor::Other => "other",
+        };
+        f.write_str(str)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Application {
+    pub key: Option<String>,
+    pub name: Option<String>,
+}
+
+/// Device the user tried to access an application from
+#[derive(Debug, Deserialize)]
+pub struct AccessDevice {
+    pub ip: Option<String>,
+    pub hostname: Option<String>,
+    pub browser: Option<String>,
+    pub os: Option<String>,
+    pub location: Option<Location>,
+}
+
+/// Device the user approved the authentication with, e.g., a phone
+#[derive(Debug, Deserialize)]
+pub struct AuthDevice {
+    pub ip: Option<String>,
+    pub name: Option<String>,
+    pub location: Option<Location>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Location {
+    pub city: Option<String>,
+    pub state: Option<String>,
+    pub country: Option<String>,
+}
+
+/// Iterates over the pages of authentication logs by following `metadata.next_offset`.
+///
+/// The iterator stops after the last page, a failed request, or a `DuoResponse::Fail`.
+pub struct AuthLogPages<'a, D: Duo + ?Sized> {
+    duo: &'a D,
+    query: AuthLogQuery,
+    next_offset: Option<Option<AuthLogOffset>>,
+}
+
+impl<'a, D: Duo + ?Sized> AuthLogPages<'a, D> {
+    pub fn new(duo: &'a D, query: AuthLogQuery) -> Self {
+        AuthLogPages {
+            duo,
+            query,
+            next_offset: Some(None),
+        }
+    }
+
+    /// Fetches all remaining pages and concatenates their events.
+    pub fn collect_all(self) -> DuoResult<DuoResponse<Vec<AuthLog>>> {
+        let mut all = Vec::new();
+        for page in self {
+            match page? {
+                DuoResponse::Ok { response, .. } => all.extend(response.auth_logs),
+                DuoResponse::Fail {
+                    code,
+                    message,
+                    message_detail,
+                } => {
+                    return Ok(DuoResponse::Fail {
+                        code,
+                        message,
+                        message_detail,
+                    })
+                }
+            }
+        }
+
+        Ok(DuoResponse::Ok {
+            response: all,
+            metadata: None,
+        })
+    }
+}
+
+impl<'a, D: Duo + ?Sized> Iterator for AuthLogPages<'a, D> {
+    type Item = DuoResult<DuoResponse<AuthLogs>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let offset = self.next_offset.take()?;
+        let res = self.duo.get_auth_logs_page(&self.query, offset.as_ref());
+        if let Ok(DuoResponse::Ok { ref response, .. }) = res {
+            self.next_offset = response.metadata.next_offset.clone().map(Some);
+        }
+        debug!(
+            "Fetched auth logs at offset {:?}; next offset is {:?}.",
+            offset, self.next_offset
+        );
+
+        Some(res)
+    }
+}
+
+/// Last use of Duo per user derived from authentication logs
+#[derive(Debug, Default)]
+pub struct AuthLogSummary {
+    last_success: HashMap<String, DateTime<Utc>>,
+    last_success_by_factor: HashMap<(String, Factor), DateTime<Utc>>,
+    unsuccessful: HashSet<String>,
+}
+
+impl AuthLogSummary {
+    pub fn new<'a, I: IntoIterator<Item = &'a AuthLog>>(logs: I) -> AuthLogSummary {
+        let mut summary = AuthLogSummary::default();
+        for log in logs {
+            let (user_id, timestamp) = match (&log.user.key, log.timestamp) {
+                (Some(user_id), Some(timestamp)) => (user_id, timestamp),
+                _ => continue,
+            };
+            match log.result {
+                AuthResult::Success => {
+                    update_latest(&mut summary.last_success, user_id.clone(), timestamp);
+                    if let Some(factor) = log.factor {
+                        update_latest(
+                            &mut summary.last_success_by_factor,
+                            (user_id.clone(), factor),
+                            timestamp,
+                        );
+                    }
+                }
+                AuthResult::Denied | AuthResult::Fraud => {
+                    summary.unsuccessful.insert(user_id.clone());
+                }
+                AuthResult::Error => {}
+            }
+        }
+
+        summary
+    }
+
+    /// Last successful login of a user
+    pub fn last_success(&self, user_id: &str) -> Option<DateTime<Utc>> {
+        self.last_success.get(user_id).cloned()
+    }
+
+    /// Last successful login of a user with a specific factor
+    pub fn last_success_by_factor(&self, user_id: &str, factor: Factor) -> Option<DateTime<Utc>> {
+        self.last_success_by_factor.get(&(user_id.to_string(), factor)).cloned()
+    }
+
+    /// Users whose logins have been denied or flagged as fraud without any successful login
+    pub fn only_unsuccessful(&self) -> Vec<&str> {
+        let mut user_ids: Vec<_> = self
+            .unsuccessful
+            .iter()
+            .filter(|x| !self.last_success.contains_key(*x))
+            .map(String::as_str)
+            .collect();
+        user_ids.sort();
+
+        user_ids
+    }
+}
+
+fn update_latest<K: std::hash::Hash + Eq>(map: &mut HashMap<K, DateTime<Utc>>, key: K, timestamp: DateTime<Utc>) {
+    let latest = map.entry(key).or_insert(timestamp);
+    if *latest < timestamp {
+        *latest = timestamp;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use spectral::prelude::*;
+
+    use super::*;
+
+    const AUTH_LOGS: &str = r#"{
+        "stat": "OK",
+        "response": {
+            "authlogs": [
+                {
+                    "access_device": {
+                        "browser": "Chrome",
+                        "hostname": null,
+                        "ip": "192.0.2.10",
+                        "location": {"city": "Ann Arbor", "country": "United States", "state": "Michigan"},
+                        "os": "Mac OS X"
+                    },
+                    "application": {"key": "DIY231J8BR23QK4UKBY8", "name": "Microsoft Azure Active Directory"},
+                    "auth_device": {"ip": "192.168.225.254", "location": {"city": null, "country": null, "state": null}, "name": "My iPhone X (734-555-2342)"},
+                    "event_type": "authentication",
+                    "factor": "duo_push",
+                    "reason": "user_approved",
+                    "result": "success",
+                    "timestamp": 1532951962,
+                    "txid": "340a23e3-23f3-4f4c-8a6c-8b1e9b4f2d5c",
+                    "user": {"key": "DU3KC77WJ06Y5HIV7XKQ", "name": "narroway@example.com"}
+                },
+                {
+                    "event_type": "authentication",
+                    "factor": "phone_call",
+                    "reason": "user_marked_fraud",
+                    "result": "fraud",
+                    "timestamp": 1532952000,
+                    "txid": "e9f1c6f5-7e1a-4a5a-b77a-2d4c9f3f7d0a",
+                    "user": {"key": "DU3KC77WJ06Y5HIV7XKR", "name": "jsmith@example.com"}
+                },
+                {
+                    "event_type": "authentication",
+                    "factor": "some_new_factor",
+                    "reason": "user_approved",
+                    "result": "success",
+                    "timestamp": 1532950000,
+                    "txid": "1f0a5dc1-6a33-4ad3-9d2e-1d3c0b8e4e71",
+                    "user": {"key": "DU3KC77WJ06Y5HIV7XKQ", "name": "narroway@example.com"}
+                }
+            ],
+            "metadata": {
+                "next_offset": ["1532951895000", "af0ba235-0b33-23c8-bc23-a31aa0231de8"],
+                "total_objects": 3
+            }
+        }
+    }"#;
+
+    #[test]
+    fn deserialize_auth_logs() {
+        let response: Result<DuoResponse<AuthLogs>, _> = serde_json::from_str(AUTH_LOGS);
+
+        assert_that(&response).is_ok();
+        let logs = response.unwrap().ok().map(|x| x.auth_logs.len());
+        assert_that(&logs).is_equal_to(Some(3));
+    }
+
+    #[test]
+    fn next_offset_is_comma_separated() {
+        let response: DuoResponse<AuthLogs> = serde_json::from_str(AUTH_LOGS).unwrap();
+
+        let next_offset = response
+            .ok()
+            .and_then(|x| x.metadata.next_offset.as_ref())
+            .map(|x| x.to_string());
+
+        assert_that(&next_offset).is_equal_to(Some("1532951895000,af0ba235-0b33-23c8-bc23-a31aa0231de8".to_string()));
+    }
+
+    #[test]
+    fn query_params_in_millis() {
+        let query = AuthLogQuery::new(Utc.timestamp(1, 0), Utc.timestamp(2, 0))
+            .limit(5000)
+            .user("DU3KC77WJ06Y5HIV7XKQ")
+            .user("DU3KC77WJ06Y5HIV7XKR");
+
+        let params = query.to_params();
+
+        assert_that(&params).contains(("mintime", "1000".to_string()));
+        assert_that(&params).contains(("limit", "1000".to_string()));
+        assert_that(&params).contains(("users", "DU3KC77WJ06Y5HIV7XKQ,DU3KC77WJ06Y5HIV7XKR".to_string()));
+    }
+
+    #[test]
+    fn summary() {
+        let response: DuoResponse<AuthLogs> = serde_json::from_str(AUTH_LOGS).unwrap();
+        let logs = &response.ok().unwrap().auth_logs;
+
+        let summary = AuthLogSummary::new(logs);
+
+        assert_that(&summary.last_success("DU3KC77WJ06Y5HIV7XKQ")).is_equal_to(Some(Utc.timestamp(1532951962, 0)));
+        assert_that(&summary.last_success_by_factor("DU3KC77WJ06Y5HIV7XKQ", Factor::Other))
+            .is_equal_to(Some(Utc.timestamp(1532950000, 0)));
+        assert_that(&summary.last_success_by_factor("DU3KC77WJ06Y5HIV7XKQ", Factor::PhoneCall)).is_none();
+        assert_that(&summary.only_unsuccessful()).is_equal_to(vec!["DU3KC77WJ06Y5HIV7XKR"]);
+    }
+}
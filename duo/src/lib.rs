@@ -5,11 +5,16 @@ use std::time::Duration;
 
 use chrono::{DateTime, Local, NaiveDateTime, Utc};
 use failure::Fail;
-use log::{debug, info, trace};
+use log::{debug, info, trace, warn};
 use reqwest::{Method, RequestBuilder, StatusCode};
-use ring::hmac;
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::{digest, hmac};
 use serde::{de::DeserializeOwned, Deserialize, Deserializer};
 
+pub mod auth_log;
+
+pub use auth_log::{AuthLog, AuthLogPages, AuthLogQuery, AuthLogSummary, AuthLogs, AuthResult, Factor};
+
 /// Result of an attempt to send meta data or a metric datum
 pub type DuoResult<T> = Result<T, DuoError>;
 
@@ -28,8 +33,74 @@ pub enum DuoError {
     /// Failed to read from Duo
     #[fail(display = "failed to process Duo response because {}", _0)]
     ReceiveError(String),
+    /// Duo throttled requests, i.e., it answered with 429 even after retrying
+    #[fail(display = "rate limited by Duo because {}", _0)]
+    RateLimited(String),
+    /// Request cannot be sent to Duo, e.g., because it sets a status only Duo may set
+    #[fail(display = "invalid request because {}", _0)]
+    InvalidRequest(String),
+}
+
+/// Controls how often and how fast requests to Duo are retried after 429 and 5xx responses.
+///
+/// The default retries at most 3 times with backoffs of up to 1, 2, and 4 s, so a rate-limited request does not stall
+/// a Lambda run for more than about 7 s. Functions with a longer timeout may raise `retries` and `max_backoff_sec` in
+/// their `DuoClientConfig`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Number of retries after the first attempt; 0 disables retrying
+    pub max_retries: u32,
+    /// Backoff before the first retry; the backoff doubles with every further retry
+    pub initial_backoff: Duration,
+    /// Upper bound for the backoff between two retries
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Never retries.
+    pub fn none() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 0,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the maximum backoff before retry number `retry`, starting at 0.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry);
+        self.initial_backoff
+            .checked_mul(factor)
+            .map(|x| x.min(self.max_backoff))
+            .unwrap_or(self.max_backoff)
+    }
+
+    /// Returns a random backoff between half of and the full `backoff(retry)` so that concurrent clients spread
+    /// their retries.
+    pub fn jittered_backoff(&self, retry: u32) -> Duration {
+        let backoff = self.backoff(retry);
+        let mut random = [0u8; 4];
+        let jitter = match SystemRandom::new().fill(&mut random) {
+            Ok(()) => f64::from(u32::from_be_bytes(random)) / f64::from(std::u32::MAX),
+            Err(_) => 1.0,
+        };
+
+        backoff / 2 + backoff.mul_f64(jitter / 2.0)
+    }
 }
 
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 3,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(8),
+        }
+    }
+}
+
+/// Default number of objects requested per page; Duo allows at most 300 users per page.
+pub const DEFAULT_PAGE_LIMIT: usize = 300;
+
 /// Generic Duo Response
 #[derive(Debug, Deserialize)]
 #[serde(tag = "stat")]
@@ -37,6 +108,9 @@ pub enum DuoError {
 pub enum DuoResponse<T> {
     Ok {
         response: T,
+        /// Paging information of paginated listings
+        #[serde(default)]
+        metadata: Option<ResponseMetadata>,
     },
     Fail {
         code: usize,
@@ -48,31 +122,137 @@ pub enum DuoResponse<T> {
 impl<T> DuoResponse<T> {
     pub fn ok(&self) -> Option<&T> {
         match self {
-            DuoResponse::Ok { response: ref data } => Some(data),
+            DuoResponse::Ok { response: ref data, .. } => Some(data),
+            DuoResponse::Fail { .. } => None,
+        }
+    }
+
+    pub fn metadata(&self) -> Option<&ResponseMetadata> {
+        match self {
+            DuoResponse::Ok { ref metadata, .. } => metadata.as_ref(),
             DuoResponse::Fail { .. } => None,
         }
     }
 }
 
+/// Paging information of a paginated listing
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+pub struct ResponseMetadata {
+    /// Offset of the next page; missing on the last page
+    pub next_offset: Option<usize>,
+    pub prev_offset: Option<usize>,
+    pub total_objects: Option<usize>,
+}
+
+/// Fetches a page of at most `limit` objects starting at an offset
+pub type PageFetcher<D, T> = fn(&D, usize, usize) -> DuoResult<DuoResponse<Vec<T>>>;
+
+/// Iterates over the pages of a paginated listing by following `metadata.next_offset`.
+///
+/// The iterator stops after the last page, a failed request, or a `DuoResponse::Fail`.
+pub struct Pages<'a, D, T> {
+    duo: &'a D,
+    fetch: PageFetcher<D, T>,
+    limit: usize,
+    next_offset: Option<usize>,
+}
+
+impl<'a, D, T> Pages<'a, D, T> {
+    /// Creates an iterator starting at offset 0 that fetches pages of `limit` objects by calling `fetch` with
+    /// `limit` and offset.
+    pub fn new(duo: &'a D, limit: usize, fetch: PageFetcher<D, T>) -> Self {
+        Pages {
+            duo,
+            fetch,
+            limit,
+            next_offset: Some(0),
+        }
+    }
+
+    /// Fetches all remaining pages and concatenates them into a single response.
+    pub fn collect_all(self) -> DuoResult<DuoResponse<Vec<T>>> {
+        let mut all = Vec::new();
+        for page in self {
+            match page? {
+                DuoResponse::Ok { response, .. } => all.extend(response),
+                fail => return Ok(fail),
+            }
+        }
+        let metadata = ResponseMetadata {
+            total_objects: Some(all.len()),
+            ..Default::default()
+        };
+
+        Ok(DuoResponse::Ok {
+            response: all,
+            metadata: Some(metadata),
+        })
+    }
+}
+
+impl<'a, D, T> Iterator for Pages<'a, D, T> {
+    type Item = DuoResult<DuoResponse<Vec<T>>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let offset = self.next_offset.take()?;
+        let res = (self.fetch)(self.duo, self.limit, offset);
+        if let Ok(ref response) = res {
+            self.next_offset = response.metadata().and_then(|x| x.next_offset);
+        }
+        debug!(
+            "Fetched page at offset {}; next offset is {:?}.",
+            offset, self.next_offset
+        );
+
+        Some(res)
+    }
+}
+
 /// Encapsulates Duo server connection configuration
 #[derive(Debug, serde_derive::Deserialize, serde_derive::Serialize, Eq, PartialEq)]
 pub struct DuoClientConfig {
     pub api_host_name: String,
     pub integration_key: String,
     pub secret_key: String,
+    /// Number of retries after 429 and 5xx responses; defaults to `duo::RetryPolicy::default()`
+    pub retries: Option<u32>,
+    /// Upper bound in sec for the backoff between two retries; defaults to `duo::RetryPolicy::default()`
+    pub max_backoff_sec: Option<u64>,
+    /// Request signing scheme; defaults to `duo::SignatureVersion::default()`
+    pub signature_version: Option<SignatureVersion>,
+}
+
+/// Scheme used to sign requests to the Duo Admin API
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde_derive::Deserialize, serde_derive::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureVersion {
+    /// Legacy HMAC-SHA1 over date, method, host, path and parameters
+    V2,
+    /// HMAC-SHA512 over date, method, host, path, parameters and the SHA-512 hashes of body and `X-Duo-*` headers
+    V5,
+}
+
+impl Default for SignatureVersion {
+    fn default() -> Self {
+        SignatureVersion::V2
+    }
 }
 
 /// Encapsulates Duo server connection.
 #[derive(Debug)]
 pub struct DuoClient {
+    base_url: String,
     api_host_name: String,
     integration_key: String,
     secret_key: String,
     client: Arc<reqwest::Client>,
+    retry_policy: RetryPolicy,
+    signature_version: SignatureVersion,
 }
 
 impl DuoClient {
-    /// Creates a new DuoClient.
+    /// Creates a new DuoClient; `api_host_name` is contacted via https unless it starts with `http://`, e.g., for
+    /// a local stand-in server in tests.
     pub fn new<S: Into<String>, T: Into<String>, U: Into<String>>(
         api_host_name: S,
         integration_key: T,
@@ -93,22 +273,66 @@ impl DuoClient {
             .build()
             .map_err(|e| DuoError::ClientError(format!("failed to build http client because {}", e.to_string())))?;
 
+        let (base_url, api_host_name) = base_url_and_host(api_host_name.into());
+
         Ok(DuoClient {
-            api_host_name: api_host_name.into(),
+            base_url,
+            api_host_name,
             integration_key: integration_key.into(),
             secret_key: secret_key.into(),
             client: Arc::new(client),
+            retry_policy: RetryPolicy::default(),
+            signature_version: SignatureVersion::default(),
         })
     }
 
-    fn get_duo_api<T: DeserializeOwned>(&self, path: &str, expected: StatusCode) -> DuoResult<DuoResponse<T>> {
-        let uri = format!("https://{}{}", self.api_host_name, path);
+    /// Creates a new DuoClient from configuration
+    pub fn from_config(config: &DuoClientConfig) -> DuoResult<DuoClient> {
+        let mut client = DuoClient::new(
+            config.api_host_name.as_str(),
+            config.integration_key.as_str(),
+            config.secret_key.as_str(),
+        )?;
+        let default = RetryPolicy::default();
+        client.set_retry_policy(RetryPolicy {
+            max_retries: config.retries.unwrap_or(default.max_retries),
+            max_backoff: config
+                .max_backoff_sec
+                .map(Duration::from_secs)
+                .unwrap_or(default.max_backoff),
+            ..default
+        });
+        if let Some(signature_version) = config.signature_version {
+            client.set_signature_version(signature_version);
+        }
+
+        Ok(client)
+    }
 
-        let req = self.client.get(&uri);
-        let req = self.sign_req(req, Method::GET, path, &HashMap::new());
-        debug!("Request: '{:?}'", req);
+    /// Sets the policy for retrying throttled and failed requests.
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.retry_policy = retry_policy;
+    }
 
-        let res = req.send();
+    /// Sets the scheme used to sign requests.
+    pub fn set_signature_version(&mut self, signature_version: SignatureVersion) {
+        self.signature_version = signature_version;
+    }
+
+    fn get_duo_api<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        params: &HashMap<&str, &str>,
+        expected: StatusCode,
+    ) -> DuoResult<DuoResponse<T>> {
+        let uri = format!("{}{}", self.base_url, path);
+
+        let res = self.send_with_retry(|| {
+            let req = self.client.get(&uri).query(params);
+            let req = self.sign_req(req, Method::GET, path, params);
+            debug!("Request: '{:?}'", req);
+            req
+        });
         match res {
             Ok(mut response) if response.status() == expected => {
                 let text = response
@@ -119,28 +343,44 @@ impl DuoClient {
                     .map_err(|e| DuoError::JsonParseError(e.to_string()))?;
                 Ok(data)
             }
-            Ok(response) => Err(DuoError::ReceiveError(format!("{}", response.status()))),
+            Ok(response) => Err(status_error(response.status())),
             Err(err) => Err(DuoError::SendError(format!("{}", err))),
         }
     }
 
+    fn get_duo_api_page<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        limit: usize,
+        offset: usize,
+    ) -> DuoResult<DuoResponse<Vec<T>>> {
+        let limit = limit.to_string();
+        let offset = offset.to_string();
+        let params: HashMap<&str, &str> = [("limit", limit.as_str()), ("offset", offset.as_str())]
+            .iter()
+            .cloned()
+            .collect();
+        self.get_duo_api(path, &params, StatusCode::OK)
+    }
+
     fn post_duo_api<T: DeserializeOwned>(
         &self,
         path: &str,
         params: &HashMap<&str, &str>,
         expected: StatusCode,
     ) -> DuoResult<DuoResponse<T>> {
-        let uri = format!("https://{}{}", self.api_host_name, path);
-
-        let req = self
-            .client
-            .post(&uri)
-            .header("Content-Type", "application/x-www-form-urlencoded")
-            .query(params);
-        let req = self.sign_req(req, Method::POST, path, params);
-        debug!("Request: '{:?}'", req);
+        let uri = format!("{}{}", self.base_url, path);
 
-        let res = req.send();
+        let res = self.send_with_retry(|| {
+            let req = self
+                .client
+                .post(&uri)
+                .header("Content-Type", "application/x-www-form-urlencoded")
+                .query(params);
+            let req = self.sign_req(req, Method::POST, path, params);
+            debug!("Request: '{:?}'", req);
+            req
+        });
         match res {
             Ok(mut response) if response.status() == expected => {
                 let text = response
@@ -151,33 +391,37 @@ impl DuoClient {
                     .map_err(|e| DuoError::JsonParseError(e.to_string()))?;
                 Ok(data)
             }
-            Ok(response) => Err(DuoError::ReceiveError(format!("{}", response.status()))),
+            Ok(response) => Err(status_error(response.status())),
             Err(err) => Err(DuoError::SendError(format!("{}", err))),
         }
     }
 
     fn delete_duo_api(&self, path: &str, expected: StatusCode) -> DuoResult<DuoResponse<()>> {
-        let uri = format!("https://{}{}", self.api_host_name, path);
-
-        let req = self
-            .client
-            .delete(&uri)
-            .header("Content-Type", "application/x-www-form-urlencoded");
+        let uri = format!("{}{}", self.base_url, path);
         let params = HashMap::new();
-        let req = self.sign_req(req, Method::DELETE, path, &params);
-        debug!("Request: '{:?}'", req);
 
-        let res = req.send();
+        let res = self.send_with_retry(|| {
+            let req = self
+                .client
+                .delete(&uri)
+                .header("Content-Type", "application/x-www-form-urlencoded");
+            let req = self.sign_req(req, Method::DELETE, path, &params);
+            debug!("Request: '{:?}'", req);
+            req
+        });
         let res = match res {
             Ok(mut response) if response.status() == expected => {
                 let text = response
                     .text()
                     .map_err(|_| DuoError::ReceiveError("failed to read response body".to_string()))?;
                 trace!("Answer: '{}'", text);
-                let data = DuoResponse::Ok { response: () };
+                let data = DuoResponse::Ok {
+                    response: (),
+                    metadata: None,
+                };
                 Ok(data)
             }
-            Ok(response) => Err(DuoError::ReceiveError(format!("{}", response.status()))),
+            Ok(response) => Err(status_error(response.status())),
             Err(err) => Err(DuoError::SendError(format!("{}", err))),
         };
 
@@ -185,6 +429,34 @@ impl DuoClient {
         res
     }
 
+    /// Sends the request built by `request` and retries it according to the retry policy as long as Duo answers
+    /// with 429 or 5xx. Every attempt builds a new request so that it carries a fresh signature date.
+    fn send_with_retry<F>(&self, request: F) -> Result<reqwest::Response, reqwest::Error>
+    where
+        F: Fn() -> RequestBuilder,
+    {
+        let mut retry = 0;
+        loop {
+            let res = request().send();
+            let retryable = match res {
+                Ok(ref response) => is_retryable(response.status()),
+                Err(_) => false,
+            };
+            if !retryable || retry >= self.retry_policy.max_retries {
+                return res;
+            }
+
+            let backoff = self.retry_policy.jittered_backoff(retry);
+            warn!(
+                "Request to Duo failed ({:?}); retrying in {} ms.",
+                res.as_ref().map(reqwest::Response::status),
+                backoff.as_millis()
+            );
+            std::thread::sleep(backoff);
+            retry += 1;
+        }
+    }
+
     fn sign_req(
         &self,
         req: RequestBuilder,
@@ -198,12 +470,42 @@ impl DuoClient {
         let params = encode_params(params);
         let canon = [now.as_str(), method, api_host_name.as_str(), path, params.as_str()];
 
-        let basic_auth = basic_auth_for_canon(&self.integration_key, &self.secret_key, &canon);
+        // All parameters are sent as query parameters, so the body is always empty and there are no X-Duo headers.
+        let basic_auth = match self.signature_version {
+            SignatureVersion::V2 => basic_auth_for_canon(&self.integration_key, &self.secret_key, &canon),
+            SignatureVersion::V5 => {
+                basic_auth_for_canon_v5(&self.integration_key, &self.secret_key, &canon, "", &HashMap::new())
+            }
+        };
 
         req.header("Date", &now).header("Authorization", &basic_auth)
     }
 }
 
+/// Splits an API host name into the base URL requests are sent to and the host name requests are signed for.
+fn base_url_and_host(api_host_name: String) -> (String, String) {
+    match api_host_name.find("://") {
+        Some(pos) => {
+            let base_url = api_host_name.trim_end_matches('/').to_string();
+            let host = base_url[pos + 3..].to_string();
+            (base_url, host)
+        }
+        None => (format!("https://{}", api_host_name), api_host_name),
+    }
+}
+
+fn is_retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+fn status_error(status: StatusCode) -> DuoError {
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        DuoError::RateLimited(format!("{}", status))
+    } else {
+        DuoError::ReceiveError(format!("{}", status))
+    }
+}
+
 fn encode_params(params: &HashMap<&str, &str>) -> String {
     let mut sorted_keys: Vec<_> = params.keys().collect();
     sorted_keys.sort();
@@ -216,10 +518,47 @@ fn encode_params(params: &HashMap<&str, &str>) -> String {
 }
 
 fn basic_auth_for_canon(integration_key: &str, secret_key: &str, canon: &[&str]) -> String {
+    sign_canon(hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, integration_key, secret_key, canon)
+}
+
+/// Extends the legacy canon by the SHA-512 hashes of the body and the `X-Duo-*` headers and signs it with SHA-512.
+fn basic_auth_for_canon_v5(
+    integration_key: &str,
+    secret_key: &str,
+    canon: &[&str],
+    body: &str,
+    x_duo_headers: &HashMap<&str, &str>,
+) -> String {
+    let body_hash = sha512_hex(body);
+    let headers_hash = sha512_hex(&canon_x_duo_headers(x_duo_headers));
+    let mut canon = canon.to_vec();
+    canon.push(body_hash.as_str());
+    canon.push(headers_hash.as_str());
+
+    sign_canon(hmac::HMAC_SHA512, integration_key, secret_key, &canon)
+}
+
+/// Joins lower cased header names and their values sorted by name with NUL bytes.
+fn canon_x_duo_headers(headers: &HashMap<&str, &str>) -> String {
+    let mut headers: Vec<_> = headers.iter().map(|(k, v)| (k.to_lowercase(), *v)).collect();
+    headers.sort();
+
+    headers
+        .iter()
+        .flat_map(|(k, v)| vec![k.as_str(), v])
+        .collect::<Vec<_>>()
+        .join("\0")
+}
+
+fn sha512_hex(data: &str) -> String {
+    hex::encode(digest::digest(&digest::SHA512, data.as_bytes()).as_ref())
+}
+
+fn sign_canon(algorithm: hmac::Algorithm, integration_key: &str, secret_key: &str, canon: &[&str]) -> String {
     let canon = canon.join("\n");
     trace!("Canon: '{}'", canon);
 
-    let s_key = hmac::Key::new(hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, secret_key.as_bytes());
+    let s_key = hmac::Key::new(algorithm, secret_key.as_bytes());
     let mut s_ctx = hmac::Context::with_key(&s_key);
     s_ctx.update(canon.as_bytes());
     let sig = s_ctx.sign();
@@ -242,6 +581,28 @@ pub struct User {
     pub status: UserStatus,
     #[serde(deserialize_with = "from_unix_timestamp")]
     pub last_login: Option<DateTime<Utc>>,
+    #[serde(default, deserialize_with = "from_unix_timestamp")]
+    pub created: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub groups: Vec<Group>,
+}
+
+impl User {
+    /// Returns `true` if the user belongs to a group named `group_name`.
+    pub fn is_member_of(&self, group_name: &str) -> bool {
+        self.groups.iter().any(|x| x.name == group_name)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Group {
+    pub group_id: String,
+    pub name: String,
+    #[serde(default)]
+    pub desc: Option<String>,
+    /// Authentication status of the group's members, e.g., `Active` or `Bypass`
+    #[serde(default)]
+    pub status: Option<String>,
 }
 
 fn from_unix_timestamp<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
@@ -256,7 +617,7 @@ where
     Ok(utc)
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum UserStatus {
     Active,
@@ -268,6 +629,60 @@ pub enum UserStatus {
     PendingDeletion,
 }
 
+impl UserStatus {
+    /// Returns `true` if the status may be set via the Admin API; Duo itself locks out users and marks them for
+    /// deletion.
+    pub fn is_settable(self) -> bool {
+        match self {
+            UserStatus::Active | UserStatus::Bypass | UserStatus::Disabled => true,
+            UserStatus::LockedOut | UserStatus::PendingDeletion => false,
+        }
+    }
+}
+
+/// Changes to a user; fields that are `None` stay unchanged.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UserUpdate {
+    pub username: Option<String>,
+    pub realname: Option<String>,
+    pub firstname: Option<String>,
+    pub lastname: Option<String>,
+    pub email: Option<String>,
+    pub notes: Option<String>,
+    pub status: Option<UserStatus>,
+}
+
+impl UserUpdate {
+    /// Creates an update that only changes the status.
+    pub fn status(status: UserStatus) -> UserUpdate {
+        UserUpdate {
+            status: Some(status),
+            ..Default::default()
+        }
+    }
+
+    fn to_params(&self) -> DuoResult<Vec<(&'static str, String)>> {
+        if let Some(status) = self.status {
+            if !status.is_settable() {
+                return Err(DuoError::InvalidRequest(format!("status '{}' cannot be set", status)));
+            }
+        }
+
+        let fields = [
+            ("username", self.username.clone()),
+            ("realname", self.realname.clone()),
+            ("firstname", self.firstname.clone()),
+            ("lastname", self.lastname.clone()),
+            ("email", self.email.clone()),
+            ("notes", self.notes.clone()),
+            ("status", self.status.map(|x| x.to_string())),
+        ];
+        let params = fields.iter().filter_map(|(k, v)| v.clone().map(|v| (*k, v))).collect();
+
+        Ok(params)
+    }
+}
+
 impl fmt::Display for UserStatus {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let str = match self {
@@ -281,35 +696,350 @@ impl fmt::Display for UserStatus {
     }
 }
 
+/// Reference to the user a phone, token or bypass code belongs to
+#[derive(Debug, Deserialize)]
+pub struct UserRef {
+    pub user_id: String,
+    pub username: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Phone {
+    pub phone_id: String,
+    pub number: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub phone_type: PhoneType,
+    pub platform: String,
+    pub activated: bool,
+    #[serde(default, deserialize_with = "from_iso_timestamp")]
+    pub last_seen: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub users: Vec<UserRef>,
+}
+
+/// Parses Duo's `last_seen` timestamps which are in UTC but lack a time zone, e.g. `2019-11-18T15:51:13`.
+fn from_iso_timestamp<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let timestamp: Option<String> = Option::deserialize(deserializer)?;
+    let utc = timestamp
+        .filter(|x| !x.is_empty())
+        .map(|x| NaiveDateTime::parse_from_str(&x, "%Y-%m-%dT%H:%M:%S"))
+        .transpose()
+        .map_err(serde::de::Error::custom)?
+        .map(|x| DateTime::from_utc(x, Utc));
+
+    Ok(utc)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PhoneType {
+    #[serde(alias = "Mobile")]
+    Mobile,
+    #[serde(alias = "Landline")]
+    Landline,
+    #[serde(other)]
+    Unknown,
+}
+
+impl fmt::Display for PhoneType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match self {
+            PhoneType::Mobile => "mobile",
+            PhoneType::Landline => "landline",
+            PhoneType::Unknown => "unknown",
+        };
+        f.write_str(str)
+    }
+}
+
+/// Hardware token
+#[derive(Debug, Deserialize)]
+pub struct Token {
+    pub token_id: String,
+    pub serial: String,
+    /// Token type, e.g., `h6` for HOTP-6 or `yk` for YubiKey AES
+    #[serde(rename = "type")]
+    pub token_type: String,
+    pub totp_step: Option<u64>,
+    #[serde(default)]
+    pub users: Vec<UserRef>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BypassCode {
+    pub bypass_code_id: String,
+    pub admin_email: Option<String>,
+    #[serde(deserialize_with = "from_unix_timestamp")]
+    pub created: Option<DateTime<Utc>>,
+    /// Missing if the bypass code never expires
+    #[serde(default, deserialize_with = "from_unix_timestamp")]
+    pub expiration: Option<DateTime<Utc>>,
+    /// Remaining number of uses; missing if the bypass code may be used an unlimited number of times
+    pub reuse_count: Option<u64>,
+    pub user: UserRef,
+}
+
 pub trait Duo {
+    /// Lists all users by fetching all pages.
     fn get_users(&self) -> DuoResult<DuoResponse<Vec<User>>>;
+    /// Lists at most `limit` users starting at `offset`; see `DuoResponse::metadata` for the next offset.
+    fn get_users_page(&self, limit: usize, offset: usize) -> DuoResult<DuoResponse<Vec<User>>>;
     fn disable_user(&self, user_id: String) -> DuoResult<DuoResponse<User>>;
     fn delete_user(&self, user_id: String) -> DuoResult<DuoResponse<()>>;
+    /// Sets the status of a user, e.g., to re-enable a disabled user; only settable statuses are allowed.
+    fn set_user_status(&self, user_id: String, status: UserStatus) -> DuoResult<DuoResponse<User>>;
+    /// Changes the fields of a user set in `update`.
+    fn update_user(&self, user_id: String, update: &UserUpdate) -> DuoResult<DuoResponse<User>>;
+    /// Creates `count` bypass codes for a user which expire after `valid_for` or never, if `None`.
+    ///
+    /// Duo's `bypass` user status has no expiry, so temporary bypasses should use expiring bypass codes instead.
+    fn create_bypass_codes(
+        &self,
+        user_id: String,
+        count: usize,
+        valid_for: Option<Duration>,
+    ) -> DuoResult<DuoResponse<Vec<String>>>;
+
+    /// Lists all phones by fetching all pages.
+    fn get_phones(&self) -> DuoResult<DuoResponse<Vec<Phone>>>;
+    /// Lists at most `limit` phones starting at `offset`; see `DuoResponse::metadata` for the next offset.
+    fn get_phones_page(&self, limit: usize, offset: usize) -> DuoResult<DuoResponse<Vec<Phone>>>;
+    fn delete_phone(&self, phone_id: String) -> DuoResult<DuoResponse<()>>;
+
+    /// Lists all hardware tokens by fetching all pages.
+    fn get_tokens(&self) -> DuoResult<DuoResponse<Vec<Token>>>;
+    /// Lists at most `limit` hardware tokens starting at `offset`; see `DuoResponse::metadata` for the next offset.
+    fn get_tokens_page(&self, limit: usize, offset: usize) -> DuoResult<DuoResponse<Vec<Token>>>;
+    fn delete_token(&self, token_id: String) -> DuoResult<DuoResponse<()>>;
+
+    /// Lists all bypass codes by fetching all pages.
+    fn get_bypass_codes(&self) -> DuoResult<DuoResponse<Vec<BypassCode>>>;
+    /// Lists at most `limit` bypass codes starting at `offset`; see `DuoResponse::metadata` for the next offset.
+    fn get_bypass_codes_page(&self, limit: usize, offset: usize) -> DuoResult<DuoResponse<Vec<BypassCode>>>;
+    fn delete_bypass_code(&self, bypass_code_id: String) -> DuoResult<DuoResponse<()>>;
+
+    /// Lists all groups by fetching all pages.
+    fn get_groups(&self) -> DuoResult<DuoResponse<Vec<Group>>>;
+    /// Lists at most `limit` groups starting at `offset`; see `DuoResponse::metadata` for the next offset.
+    fn get_groups_page(&self, limit: usize, offset: usize) -> DuoResult<DuoResponse<Vec<Group>>>;
+    /// Lists all groups a user belongs to by fetching all pages.
+    fn get_user_groups(&self, user_id: String) -> DuoResult<DuoResponse<Vec<Group>>>;
+    /// Looks up users by username; the result contains at most one user.
+    fn find_users_by_username(&self, username: String) -> DuoResult<DuoResponse<Vec<User>>>;
+    /// Looks up users by email address.
+    fn find_users_by_email(&self, email: String) -> DuoResult<DuoResponse<Vec<User>>>;
+
+    /// Lists all authentication log events matching `query` by following the cursor across all pages.
+    fn get_auth_logs(&self, query: AuthLogQuery) -> DuoResult<DuoResponse<Vec<AuthLog>>>;
+    /// Lists a single page of authentication log events; pass the previous page's `next_offset` to continue.
+    fn get_auth_logs_page(
+        &self,
+        query: &AuthLogQuery,
+        next_offset: Option<&auth_log::AuthLogOffset>,
+    ) -> DuoResult<DuoResponse<AuthLogs>>;
+}
+
+impl DuoClient {
+    /// Iterates over all users in pages of `limit` users.
+    pub fn user_pages(&self, limit: usize) -> Pages<'_, DuoClient, User> {
+        Pages::new(self, limit, DuoClient::get_users_page)
+    }
+
+    /// Iterates over all phones in pages of `limit` phones.
+    pub fn phone_pages(&self, limit: usize) -> Pages<'_, DuoClient, Phone> {
+        Pages::new(self, limit, DuoClient::get_phones_page)
+    }
+
+    /// Iterates over all hardware tokens in pages of `limit` tokens.
+    pub fn token_pages(&self, limit: usize) -> Pages<'_, DuoClient, Token> {
+        Pages::new(self, limit, DuoClient::get_tokens_page)
+    }
+
+    /// Iterates over all bypass codes in pages of `limit` bypass codes.
+    pub fn bypass_code_pages(&self, limit: usize) -> Pages<'_, DuoClient, BypassCode> {
+        Pages::new(self, limit, DuoClient::get_bypass_codes_page)
+    }
+
+    /// Iterates over all groups in pages of `limit` groups.
+    pub fn group_pages(&self, limit: usize) -> Pages<'_, DuoClient, Group> {
+        Pages::new(self, limit, DuoClient::get_groups_page)
+    }
 }
 
 impl Duo for DuoClient {
     fn get_users(&self) -> DuoResult<DuoResponse<Vec<User>>> {
-        self.get_duo_api("/admin/v1/users", StatusCode::OK)
+        self.user_pages(DEFAULT_PAGE_LIMIT).collect_all()
+    }
+
+    fn get_users_page(&self, limit: usize, offset: usize) -> DuoResult<DuoResponse<Vec<User>>> {
+        self.get_duo_api_page("/admin/v1/users", limit, offset)
     }
 
     fn disable_user(&self, user_id: String) -> DuoResult<DuoResponse<User>> {
-        let path = format!("/admin/v1/users/{}", user_id);
-        let disabled = UserStatus::Disabled.to_string();
-        let params: HashMap<&str, &str> = [("status", disabled.as_str())].iter().cloned().collect();
-        self.post_duo_api(&path, &params, StatusCode::OK)
+        self.set_user_status(user_id, UserStatus::Disabled)
     }
 
     fn delete_user(&self, user_id: String) -> DuoResult<DuoResponse<()>> {
         let path = format!("/admin/v1/users/{}", user_id);
         self.delete_duo_api(&path, StatusCode::OK)
     }
+
+    fn set_user_status(&self, user_id: String, status: UserStatus) -> DuoResult<DuoResponse<User>> {
+        self.update_user(user_id, &UserUpdate::status(status))
+    }
+
+    fn update_user(&self, user_id: String, update: &UserUpdate) -> DuoResult<DuoResponse<User>> {
+        let path = format!("/admin/v1/users/{}", user_id);
+        let params = update.to_params()?;
+        let params: HashMap<&str, &str> = params.iter().map(|(k, v)| (*k, v.as_str())).collect();
+        self.post_duo_api(&path, &params, StatusCode::OK)
+    }
+
+    fn create_bypass_codes(
+        &self,
+        user_id: String,
+        count: usize,
+        valid_for: Option<Duration>,
+    ) -> DuoResult<DuoResponse<Vec<String>>> {
+        let path = format!("/admin/v1/users/{}/bypass_codes", user_id);
+        let count = count.to_string();
+        // Duo treats 0 as codes that never expire.
+        let valid_secs = valid_for.map(|x| x.as_secs()).unwrap_or(0).to_string();
+        let params: HashMap<&str, &str> = [("count", count.as_str()), ("valid_secs", valid_secs.as_str())]
+            .iter()
+            .cloned()
+            .collect();
+        self.post_duo_api(&path, &params, StatusCode::OK)
+    }
+
+    fn get_phones(&self) -> DuoResult<DuoResponse<Vec<Phone>>> {
+        self.phone_pages(DEFAULT_PAGE_LIMIT).collect_all()
+    }
+
+    fn get_phones_page(&self, limit: usize, offset: usize) -> DuoResult<DuoResponse<Vec<Phone>>> {
+        self.get_duo_api_page("/admin/v1/phones", limit, offset)
+    }
+
+    fn delete_phone(&self, phone_id: String) -> DuoResult<DuoResponse<()>> {
+        let path = format!("/admin/v1/phones/{}", phone_id);
+        self.delete_duo_api(&path, StatusCode::OK)
+    }
+
+    fn get_tokens(&self) -> DuoResult<DuoResponse<Vec<Token>>> {
+        self.token_pages(DEFAULT_PAGE_LIMIT).collect_all()
+    }
+
+    fn get_tokens_page(&self, limit: usize, offset: usize) -> DuoResult<DuoResponse<Vec<Token>>> {
+        self.get_duo_api_page("/admin/v1/tokens", limit, offset)
+    }
+
+    fn delete_token(&self, token_id: String) -> DuoResult<DuoResponse<()>> {
+        let path = format!("/admin/v1/tokens/{}", token_id);
+        self.delete_duo_api(&path, StatusCode::OK)
+    }
+
+    fn get_bypass_codes(&self) -> DuoResult<DuoResponse<Vec<BypassCode>>> {
+        self.bypass_code_pages(DEFAULT_PAGE_LIMIT).collect_all()
+    }
+
+    fn get_bypass_codes_page(&self, limit: usize, offset: usize) -> DuoResult<DuoResponse<Vec<BypassCode>>> {
+        self.get_duo_api_page("/admin/v1/bypass_codes", limit, offset)
+    }
+
+    fn delete_bypass_code(&self, bypass_code_id: String) -> DuoResult<DuoResponse<()>> {
+        let path = format!("/admin/v1/bypass_codes/{}", bypass_code_id);
+        self.delete_duo_api(&path, StatusCode::OK)
+    }
+
+    fn get_groups(&self) -> DuoResult<DuoResponse<Vec<Group>>> {
+        self.group_pages(DEFAULT_PAGE_LIMIT).collect_all()
+    }
+
+    fn get_groups_page(&self, limit: usize, offset: usize) -> DuoResult<DuoResponse<Vec<Group>>> {
+        self.get_duo_api_page("/admin/v1/groups", limit, offset)
+    }
+
+    fn get_user_groups(&self, user_id: String) -> DuoResult<DuoResponse<Vec<Group>>> {
+        let path = format!("/admin/v1/users/{}/groups", user_id);
+        let mut groups = Vec::new();
+        let mut next_offset = Some(0);
+        while let Some(offset) = next_offset {
+            match self.get_duo_api_page(&path, DEFAULT_PAGE_LIMIT, offset)? {
+                DuoResponse::Ok { response, metadata } => {
+                    groups.extend(response);
+                    next_offset = metadata.and_then(|x| x.next_offset);
+                }
+                fail => return Ok(fail),
+            }
+        }
+
+        Ok(DuoResponse::Ok {
+            response: groups,
+            metadata: None,
+        })
+    }
+
+    fn find_users_by_username(&self, username: String) -> DuoResult<DuoResponse<Vec<User>>> {
+        let params: HashMap<&str, &str> = [("username", username.as_str())].iter().cloned().collect();
+        self.get_duo_api("/admin/v1/users", &params, StatusCode::OK)
+    }
+
+    fn find_users_by_email(&self, email: String) -> DuoResult<DuoResponse<Vec<User>>> {
+        let params: HashMap<&str, &str> = [("email", email.as_str())].iter().cloned().collect();
+        self.get_duo_api("/admin/v1/users", &params, StatusCode::OK)
+    }
+
+    fn get_auth_logs(&self, query: AuthLogQuery) -> DuoResult<DuoResponse<Vec<AuthLog>>> {
+        AuthLogPages::new(self, query).collect_all()
+    }
+
+    fn get_auth_logs_page(
+        &self,
+        query: &AuthLogQuery,
+        next_offset: Option<&auth_log::AuthLogOffset>,
+    ) -> DuoResult<DuoResponse<AuthLogs>> {
+        let mut params = query.to_params();
+        if let Some(next_offset) = next_offset {
+            params.push(("next_offset", next_offset.to_string()));
+        }
+        let params: HashMap<&str, &str> = params.iter().map(|(k, v)| (*k, v.as_str())).collect();
+        self.get_duo_api("/admin/v2/logs/authentication", &params, StatusCode::OK)
+    }
+}
+
+pub mod stand_in {
+    //! Helpers for testing against a Duo stand-in, e.g., `testing::duo::DuoServer`.
+
+    use super::*;
+
+    /// Creates a client for the stand-in at `url` that retries twice with millisecond backoffs so tests stay fast.
+    pub fn client_for<S: Into<String>, T: Into<String>, U: Into<String>>(
+        url: S,
+        integration_key: T,
+        secret_key: U,
+    ) -> DuoClient {
+        let mut client = DuoClient::new(url, integration_key, secret_key).expect("Failed to create Duo Client");
+        client.set_retry_policy(RetryPolicy {
+            max_retries: 2,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(2),
+        });
+
+        client
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use std::env;
 
+    use chrono::TimeZone;
     use spectral::prelude::*;
+    use testing::duo::{user_fixture, DuoServer};
 
     use super::*;
 
@@ -334,6 +1064,425 @@ mod tests {
         assert_that(&basic_auth.as_str()).is_equal_to(&expected);
     }
 
+    #[test]
+    fn basic_auth_for_canon_v5_test() {
+        testing::setup();
+
+        let expected = "Basic RElXSjhYNkFFWU9SNU9NQzZUUTE6ODAzNjczYTQ2MzcwNDg5ZGViYjNlNGNhNzYyZjIwNTQ0YzM1ZWY2MWNiZjYyMWIyMDI3NDhiZWU5ZGYyNzVlMmZiMWVlNDdhNTI2ODVjNmE3ZjU0Y2QxM2Y2MzBhNzE4Y2Y2NTMwMzc4NjgwZmQ0MTVlMWNmYTZmZTJjYzk2ZWU=";
+
+        let integration_key = "DIWJ8X6AEYOR5OMC6TQ1";
+        let secret_key = "Zh5eGmUq9zpfQnyUIu5OL9iWoMMv5ZNmk3zLJ4Ep";
+
+        let now = "Tue, 21 Aug 2012 17:29:18 -0000";
+        let method = Method::POST.as_str();
+        let host = "api-XXXXXXXX.duosecurity.com".to_lowercase();
+        let path = "/admin/v1/users";
+        let params = "state=disabled";
+        let canon = [now, method, host.as_str(), path, params];
+
+        let basic_auth = basic_auth_for_canon_v5(integration_key, secret_key, &canon, "", &HashMap::new());
+
+        assert_that(&basic_auth.as_str()).is_equal_to(expected);
+    }
+
+    #[test]
+    fn basic_auth_for_canon_v5_with_body_and_headers_test() {
+        testing::setup();
+
+        let expected = "Basic RElXSjhYNkFFWU9SNU9NQzZUUTE6MTVlZjU2NzRhZjIyYzhiODg5MTZiNTQ5YWUyMWNmNmY4NjUyOWU4N2Q2OTI4YzRlZjI4ZThlMmE2MzUxZmYzOGY1YzAwYmM1YmI5MDQzMDgyZGZhN2UxN2VkNzAzODE1ZDNkNjEwZjYyMDBhZmIxYTExNGJkYmVhMDNmMzFmOTg=";
+
+        let integration_key = "DIWJ8X6AEYOR5OMC6TQ1";
+        let secret_key = "Zh5eGmUq9zpfQnyUIu5OL9iWoMMv5ZNmk3zLJ4Ep";
+
+        let now = "Tue, 21 Aug 2012 17:29:18 -0000";
+        let method = Method::POST.as_str();
+        let host = "api-XXXXXXXX.duosecurity.com".to_lowercase();
+        let path = "/admin/v1/users";
+        let canon = [now, method, host.as_str(), path, ""];
+        let body = r#"{"state":"disabled"}"#;
+        let headers: HashMap<&str, &str> = [("X-Duo-Test", "value")].iter().cloned().collect();
+
+        let basic_auth = basic_auth_for_canon_v5(integration_key, secret_key, &canon, body, &headers);
+
+        assert_that(&basic_auth.as_str()).is_equal_to(expected);
+    }
+
+    fn user(user_id: &str) -> User {
+        User {
+            user_id: user_id.to_string(),
+            username: user_id.to_string(),
+            realname: None,
+            email: format!("{}@example.com", user_id),
+            is_enrolled: true,
+            status: UserStatus::Active,
+            last_login: None,
+            created: None,
+            groups: Vec::new(),
+        }
+    }
+
+    /// Pretends to hold 5 users and answers like Duo, i.e., with `next_offset` unless it is the last page.
+    fn fetch_users_page(total: &usize, limit: usize, offset: usize) -> DuoResult<DuoResponse<Vec<User>>> {
+        let end = (offset + limit).min(*total);
+        let users = (offset..end).map(|x| user(&format!("user-{}", x))).collect();
+        let metadata = ResponseMetadata {
+            next_offset: if end < *total { Some(end) } else { None },
+            prev_offset: Some(offset),
+            total_objects: Some(*total),
+        };
+
+        Ok(DuoResponse::Ok {
+            response: users,
+            metadata: Some(metadata),
+        })
+    }
+
+    #[test]
+    fn base_url_defaults_to_https() {
+        let (base_url, host) = base_url_and_host("api-XXXXXXXX.duosecurity.com".to_string());
+
+        assert_that(&base_url.as_str()).is_equal_to("https://api-XXXXXXXX.duosecurity.com");
+        assert_that(&host.as_str()).is_equal_to("api-XXXXXXXX.duosecurity.com");
+    }
+
+    #[test]
+    fn base_url_with_http_scheme() {
+        let (base_url, host) = base_url_and_host("http://127.0.0.1:8080/".to_string());
+
+        assert_that(&base_url.as_str()).is_equal_to("http://127.0.0.1:8080");
+        assert_that(&host.as_str()).is_equal_to("127.0.0.1:8080");
+    }
+
+    #[test]
+    fn retry_policy_backoff() {
+        let policy = RetryPolicy::default();
+
+        assert_that(&policy.backoff(0)).is_equal_to(Duration::from_secs(1));
+        assert_that(&policy.backoff(3)).is_equal_to(Duration::from_secs(8));
+        assert_that(&policy.backoff(10)).is_equal_to(Duration::from_secs(8));
+        assert_that(&policy.backoff(40)).is_equal_to(Duration::from_secs(8));
+    }
+
+    #[test]
+    fn retry_policy_jittered_backoff() {
+        let policy = RetryPolicy::default();
+
+        for retry in 0..8 {
+            let backoff = policy.jittered_backoff(retry);
+            assert_that(&backoff).is_greater_than_or_equal_to(policy.backoff(retry) / 2);
+            assert_that(&backoff).is_less_than_or_equal_to(policy.backoff(retry));
+        }
+    }
+
+    #[test]
+    fn rate_limited_and_server_errors_are_retryable() {
+        assert_that(&is_retryable(StatusCode::TOO_MANY_REQUESTS)).is_true();
+        assert_that(&is_retryable(StatusCode::SERVICE_UNAVAILABLE)).is_true();
+        assert_that(&is_retryable(StatusCode::BAD_REQUEST)).is_false();
+    }
+
+    #[test]
+    fn rate_limited_status_error() {
+        let err = status_error(StatusCode::TOO_MANY_REQUESTS);
+
+        match err {
+            DuoError::RateLimited(_) => {}
+            _ => panic!("expected rate limiting error, got {:?}", err),
+        }
+    }
+
+    #[test]
+    fn deserialize_response_with_metadata() {
+        let json = r#"{"stat": "OK", "response": [], "metadata": {"next_offset": 300, "prev_offset": 0, "total_objects": 612}}"#;
+
+        let response: Result<DuoResponse<Vec<User>>, _> = serde_json::from_str(json);
+
+        assert_that(&response)
+            .is_ok()
+            .map(|x| &x.metadata().unwrap().next_offset)
+            .is_equal_to(Some(300));
+    }
+
+    #[test]
+    fn deserialize_response_without_metadata() {
+        let json = r#"{"stat": "OK", "response": []}"#;
+
+        let response: Result<DuoResponse<Vec<User>>, _> = serde_json::from_str(json);
+
+        assert_that(&response.unwrap().metadata()).is_none();
+    }
+
+    #[test]
+    fn pages_follow_next_offset() {
+        let total = 5;
+
+        let pages: Vec<_> = Pages::new(&total, 2, fetch_users_page).collect();
+
+        assert_that(&pages).has_length(3);
+        assert_that(pages[2].as_ref().unwrap().ok().unwrap()).has_length(1);
+    }
+
+    #[test]
+    fn pages_collect_all() {
+        let total = 5;
+
+        let response = Pages::new(&total, 2, fetch_users_page).collect_all();
+
+        assert_that(&response).is_ok();
+        let users = response.unwrap().ok().map(|x| x.len());
+        assert_that(&users).is_equal_to(Some(5));
+    }
+
+    #[test]
+    fn deserialize_user_with_groups() {
+        let json = r#"{
+            "user_id": "DU3RP9I2WOC59VZX672N",
+            "username": "jsmith",
+            "realname": "Joe Smith",
+            "email": "jsmith@example.com",
+            "is_enrolled": true,
+            "status": "active",
+            "last_login": 1343921403,
+            "created": 1343921400,
+            "groups": [{"desc": "People with hardware tokens", "group_id": "DGXXXXXXXXXXXXXXXXXX", "name": "token_users", "status": "Active"}]
+        }"#;
+
+        let user: Result<User, _> = serde_json::from_str(json);
+
+        assert_that(&user).is_ok();
+        let user = user.unwrap();
+        assert_that(&user.is_member_of("token_users")).is_true();
+        assert_that(&user.is_member_of("admins")).is_false();
+    }
+
+    #[test]
+    fn deserialize_phone() {
+        let json = r#"{
+            "activated": true,
+            "capabilities": ["push", "sms", "phone", "mobile_otp"],
+            "extension": "",
+            "last_seen": "2019-11-18T15:51:13",
+            "name": "",
+            "number": "+15555550100",
+            "phone_id": "DPFZRS9FB0D46QFTM899",
+            "platform": "Google Android",
+            "type": "Mobile",
+            "users": [{"user_id": "DUJZ2U4L80HT45MQ4EOQ", "username": "jsmith"}]
+        }"#;
+
+        let phone: Result<Phone, _> = serde_json::from_str(json);
+
+        assert_that(&phone)
+            .is_ok()
+            .map(|x| &x.last_seen)
+            .is_equal_to(Some(Utc.ymd(2019, 11, 18).and_hms(15, 51, 13)));
+    }
+
+    #[test]
+    fn deserialize_phone_never_seen() {
+        let json = r#"{
+            "activated": false,
+            "last_seen": "",
+            "name": "desk",
+            "number": "+15555550101",
+            "phone_id": "DPFZRS9FB0D46QFTM890",
+            "platform": "Unknown",
+            "type": "Landline",
+            "users": []
+        }"#;
+
+        let phone: Result<Phone, _> = serde_json::from_str(json);
+
+        assert_that(&phone).is_ok().map(|x| &x.last_seen).is_none();
+    }
+
+    #[test]
+    fn deserialize_token() {
+        let json = r#"{
+            "serial": "0",
+            "token_id": "DHIZ34ALBA2445ND4AI2",
+            "type": "d1",
+            "totp_step": null,
+            "users": [{"user_id": "DUJZ2U4L80HT45MQ4EOQ", "username": "jsmith"}]
+        }"#;
+
+        let token: Result<Token, _> = serde_json::from_str(json);
+
+        assert_that(&token).is_ok().map(|x| &x.users).has_length(1);
+    }
+
+    #[test]
+    fn deserialize_bypass_code() {
+        let json = r#"{
+            "admin_email": "ejones@example.com",
+            "bypass_code_id": "DBOJ5RLPUXO4BSWPI1D0",
+            "created": 1559855464,
+            "expiration": null,
+            "reuse_count": null,
+            "user": {"user_id": "DUJZ2U4L80HT45MQ4EOQ", "username": "jsmith"}
+        }"#;
+
+        let bypass_code: Result<BypassCode, _> = serde_json::from_str(json);
+
+        assert_that(&bypass_code).is_ok().map(|x| &x.expiration).is_none();
+    }
+
+    fn stand_in_server() -> DuoServer {
+        let users = (0..5)
+            .map(|i| user_fixture(&format!("DU{}", i), &format!("user-{}", i), Some(1_598_889_600)))
+            .collect();
+
+        testing::duo::stand_in_server(users)
+    }
+
+    fn client_for(server: &DuoServer) -> DuoClient {
+        stand_in::client_for(server.url(), server.integration_key(), server.secret_key())
+    }
+
+    #[test]
+    fn get_users_from_server_follows_pages() {
+        testing::setup();
+        let server = stand_in_server();
+        let client = client_for(&server);
+
+        let pages: Vec<_> = client.user_pages(2).collect();
+        let users = client.get_users();
+
+        assert_that(&pages).has_length(3);
+        assert_that(&users.unwrap().ok().map(|x| x.len())).is_equal_to(Some(5));
+        assert_that(&server.requests().iter().all(|x| x.authenticated)).is_true();
+    }
+
+    #[test]
+    fn disable_and_delete_user_on_server() {
+        testing::setup();
+        let server = stand_in_server();
+        let client = client_for(&server);
+
+        let disabled = client.disable_user("DU1".to_string());
+        let deleted = client.delete_user("DU2".to_string());
+
+        assert_that(&disabled).is_ok();
+        assert_that(&deleted).is_ok();
+        let users = server.users();
+        assert_that(&users).has_length(4);
+        assert_that(&users[1]["status"].as_str()).is_equal_to(Some("disabled"));
+    }
+
+    #[test]
+    fn re_enable_disabled_user_on_server() {
+        testing::setup();
+        let server = stand_in_server();
+        let client = client_for(&server);
+
+        let disabled = client.disable_user("DU1".to_string());
+        let enabled = client.set_user_status("DU1".to_string(), UserStatus::Active);
+
+        assert_that(&disabled).is_ok();
+        assert_that(&enabled.unwrap().ok().map(|x| x.status)).is_equal_to(Some(UserStatus::Active));
+        assert_that(&server.users()[1]["status"].as_str()).is_equal_to(Some("active"));
+    }
+
+    #[test]
+    fn update_user_on_server() {
+        testing::setup();
+        let server = stand_in_server();
+        let client = client_for(&server);
+        let update = UserUpdate {
+            realname: Some("Jane Doe".to_string()),
+            notes: Some("restored by security-watchtower".to_string()),
+            ..Default::default()
+        };
+
+        let updated = client.update_user("DU1".to_string(), &update);
+
+        assert_that(&updated.unwrap().ok().and_then(|x| x.realname.clone())).is_equal_to(Some("Jane Doe".to_string()));
+        assert_that(&server.users()[1]["notes"].as_str()).is_equal_to(Some("restored by security-watchtower"));
+    }
+
+    #[test]
+    fn locked_out_status_cannot_be_set() {
+        testing::setup();
+        let server = stand_in_server();
+        let client = client_for(&server);
+
+        let res = client.set_user_status("DU1".to_string(), UserStatus::LockedOut);
+
+        match res {
+            Err(DuoError::InvalidRequest(_)) => {}
+            _ => panic!("expected invalid request error, got {:?}", res),
+        }
+        assert_that(&server.requests()).is_empty();
+    }
+
+    #[test]
+    fn create_expiring_bypass_codes_on_server() {
+        testing::setup();
+        let server = stand_in_server();
+        let client = client_for(&server);
+
+        let codes = client.create_bypass_codes("DU1".to_string(), 2, Some(Duration::from_secs(3600)));
+
+        assert_that(&codes.unwrap().ok().map(|x| x.len())).is_equal_to(Some(2));
+        let params = &server.requests()[0].params;
+        assert_that(params).contains(("valid_secs".to_string(), "3600".to_string()));
+    }
+
+    #[test]
+    fn retries_rate_limited_and_failed_requests() {
+        testing::setup();
+        let server = stand_in_server();
+        let client = client_for(&server);
+        server.fail_next(429);
+        server.fail_next(500);
+
+        let users = client.get_users_page(10, 0);
+
+        assert_that(&users).is_ok();
+        assert_that(&server.requests()).has_length(3);
+    }
+
+    #[test]
+    fn rate_limited_after_exhausting_retries() {
+        testing::setup();
+        let server = stand_in_server();
+        let client = client_for(&server);
+        for _ in 0..3 {
+            server.fail_next(429);
+        }
+
+        let users = client.get_users_page(10, 0);
+
+        match users {
+            Err(DuoError::RateLimited(_)) => {}
+            _ => panic!("expected rate limiting error, got {:?}", users),
+        }
+    }
+
+    #[test]
+    fn server_accepts_v5_signatures() {
+        testing::setup();
+        let server = stand_in_server();
+        let mut client = client_for(&server);
+        client.set_signature_version(SignatureVersion::V5);
+
+        let users = client.find_users_by_username("user-3".to_string());
+
+        assert_that(&users.unwrap().ok().map(|x| x.len())).is_equal_to(Some(1));
+    }
+
+    #[test]
+    fn server_rejects_wrong_secret_key() {
+        testing::setup();
+        let server = stand_in_server();
+        let client =
+            DuoClient::new(server.url(), server.integration_key(), "wrong").expect("Failed to create Duo Client");
+
+        let users = client.get_users_page(10, 0);
+
+        assert_that(&users).is_err();
+    }
+
     #[test]
     #[ignore]
     fn get_users() {
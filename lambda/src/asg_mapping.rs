@@ -1,6 +1,7 @@
 use serde_derive::{Deserialize, Serialize};
 
-#[derive(PartialEq, Deserialize, Serialize, Debug)]
+/// Maps AWS ASG names to the `asg` tag and host name prefix used in Bosun.
+#[derive(PartialEq, Deserialize, Serialize, Debug, Default)]
 pub struct Mappings {
     #[serde(rename = "mapping")]
     pub items: Vec<Mapping>,
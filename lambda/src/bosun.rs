@@ -1,42 +1,254 @@
-use crate::config::BosunConfig;
+use crate::config::{BosunConfig, MetricSinkConfig};
+use crate::error::LambdaError;
 use crate::metrics;
-use bosun::{Bosun, BosunClient, Metadata};
-use failure::Error;
+use crate::sink::CloudWatchSink;
+use aws::AwsClientConfig;
+use bosun::{
+    registry::MetricRegistry,
+    sink::{MetricSink, OpenTsdbSink, PushgatewaySink},
+    spool::Spool,
+    AlertStatus, Annotation, AnnotationQuery, Bosun, BosunClient, BosunError, BosunResult, ClientIdentity,
+    ConnectionConfig, Datum, ExprResult, Incident, Metadata, RetryPolicy, Silence, SilenceInfo, Silences, Tags,
+};
+use failure::{Error, Fail};
 use lambda_runtime::Context;
-use log::debug;
+use log::{debug, warn};
+use reqwest::StatusCode;
+use std::time::Duration;
 
-pub fn init(config: &BosunConfig, ctx: &Context) -> Result<impl Bosun, Error> {
+/// Sets silences in Bosun, but sends metric metadata and data to the configured metric sink.
+pub struct MonitoringClient<B: Bosun> {
+    bosun: B,
+    /// `None` sends metric metadata and data to Bosun, too.
+    sink: Option<Box<dyn MetricSink>>,
+}
+
+impl<B: Bosun> MonitoringClient<B> {
+    pub fn new(bosun: B, sink: Option<Box<dyn MetricSink>>) -> MonitoringClient<B> {
+        MonitoringClient { bosun, sink }
+    }
+}
+
+impl<B: Bosun> Bosun for MonitoringClient<B> {
+    fn emit_metadata(&self, metadata: &Metadata) -> BosunResult {
+        match self.sink {
+            Some(ref sink) => sink.emit_metadata(metadata),
+            None => self.bosun.emit_metadata(metadata),
+        }
+    }
+
+    fn emit_datum(&self, datum: &Datum) -> BosunResult {
+        match self.sink {
+            Some(ref sink) => sink.emit_data(std::slice::from_ref(datum)),
+            None => self.bosun.emit_datum(datum),
+        }
+    }
+
+    fn emit_data(&self, data: &[Datum]) -> BosunResult {
+        match self.sink {
+            Some(ref sink) => sink.emit_data(data),
+            None => self.bosun.emit_data(data),
+        }
+    }
+
+    fn set_silence(&self, silence: &Silence) -> BosunResult {
+        self.bosun.set_silence(silence)
+    }
+
+    fn list_silences(&self) -> Result<Silences, BosunError> {
+        self.bosun.list_silences()
+    }
+
+    fn get_silence(&self, id: &str) -> Result<Option<SilenceInfo>, BosunError> {
+        self.bosun.get_silence(id)
+    }
+
+    fn clear_silence(&self, id: &str) -> BosunResult {
+        self.bosun.clear_silence(id)
+    }
+
+    fn create_annotation(&self, annotation: &Annotation) -> Result<Annotation, BosunError> {
+        self.bosun.create_annotation(annotation)
+    }
+
+    fn update_annotation(&self, annotation: &Annotation) -> Result<Annotation, BosunError> {
+        self.bosun.update_annotation(annotation)
+    }
+
+    fn list_annotations(&self, query: &AnnotationQuery) -> Result<Vec<Annotation>, BosunError> {
+        self.bosun.list_annotations(query)
+    }
+
+    fn open_incidents(&self) -> Result<Vec<Incident>, BosunError> {
+        self.bosun.open_incidents()
+    }
+
+    fn alert_status(&self, tags: &Tags) -> Result<AlertStatus, BosunError> {
+        self.bosun.alert_status(tags)
+    }
+
+    fn evaluate_expression(&self, expr: &str) -> Result<ExprResult, BosunError> {
+        self.bosun.evaluate_expression(expr)
+    }
+
+    fn send_to_bosun_api(&self, path: &str, json: &str, expected: StatusCode) -> BosunResult {
+        self.bosun.send_to_bosun_api(path, json, expected)
+    }
+}
+
+pub fn init(config: &BosunConfig, aws_client_config: &AwsClientConfig, ctx: &Context) -> Result<impl Bosun, Error> {
     let mut tags = config.tags.clone();
     tags.insert("host".to_string(), "lambda".to_string());
     tags.insert("function_name".to_string(), ctx.function_name.to_string());
+    let timeout = config.timeout.unwrap_or(3);
 
-    let mut bosun = BosunClient::with_tags(config.host.as_str(), config.timeout.unwrap_or(3), tags);
+    let mut bosun = BosunClient::with_tags(config.host.as_str(), timeout, tags.clone());
     bosun.set_basic_auth(config.user.clone(), Some(config.password.clone()));
+    bosun.set_connection(connection_config(config)?)?;
+    if let Some(retries) = config.retries {
+        bosun.set_retry_policy(RetryPolicy {
+            max_retries: retries,
+            ..Default::default()
+        });
+    }
+    if let Some(ref spool_file) = config.spool_file {
+        bosun.set_spool(Spool::new(spool_file));
+        // Replaying must not prevent this invocation from handling its event; failed requests stay in the spool.
+        if let Err(err) = bosun.replay_spool() {
+            warn!("Failed to replay spool '{}' because {}.", spool_file, err);
+        }
+    }
 
-    debug!("Initialized bosun.");
+    let sink: Option<Box<dyn MetricSink>> = match config.sink {
+        MetricSinkConfig::Bosun => None,
+        MetricSinkConfig::OpenTsdb { ref host } => Some(Box::new(OpenTsdbSink::new(host, timeout, tags)?)),
+        MetricSinkConfig::PrometheusPushgateway { ref url, ref job } => {
+            Some(Box::new(PushgatewaySink::new(url, job, timeout, tags)?))
+        }
+        MetricSinkConfig::CloudWatch { ref namespace } => {
+            Some(Box::new(CloudWatchSink::new(aws_client_config, namespace, tags)))
+        }
+    };
+    let bosun = MonitoringClient::new(bosun, sink);
+
+    debug!("Initialized bosun with metric sink '{:?}'.", config.sink);
     Ok(bosun)
 }
 
-pub fn send_metadata<T: Bosun>(bosun: &T, metadatas: &[Metadata]) -> Result<(), Error> {
-    for metadata in metadatas {
-        bosun.emit_metadata(&metadata)?;
+/// Reads the CA bundle and client identity files, if configured.
+pub fn connection_config(config: &BosunConfig) -> Result<ConnectionConfig, Error> {
+    let mut connection = ConnectionConfig {
+        connect_timeout: config.connect_timeout.map(Duration::from_secs),
+        proxy: config.proxy.clone(),
+        ..Default::default()
+    };
+
+    if let Some(ref tls) = config.tls {
+        if let Some(ref ca_file) = tls.ca_file {
+            let pem = read_file(ca_file)?;
+            connection = connection.add_ca_bundle(&pem);
+        }
+        if let Some(ref client_identity_file) = tls.client_identity_file {
+            connection.client_identity = Some(ClientIdentity {
+                pkcs12: read_file(client_identity_file)?,
+                password: tls.client_identity_password.clone().unwrap_or_default(),
+            });
+        }
     }
 
-    let metadata = Metadata::new(
-        metrics::LAMBDA_INVOCATION_COUNT,
-        "rate",
-        "Invocations",
-        "AWS Lambda function invocation counter",
-    );
-    bosun.emit_metadata(&metadata)?;
+    Ok(connection)
+}
+
+fn read_file(path: &str) -> Result<Vec<u8>, Error> {
+    let content = std::fs::read(path).map_err(|e| e.context(LambdaError::FailedReadFile(path.to_string())))?;
 
-    let metadata = Metadata::new(
-        metrics::LAMBDA_INVOCATION_RESULT,
-        "gauge",
-        "Result",
-        "AWS Lambda function invocation result code [0 = success, >0 = failure]",
-    );
-    bosun.emit_metadata(&metadata)?;
+    Ok(content)
+}
+
+/// Sends the metadata of all metrics in `registry` as well as of the invocation metrics every function emits.
+pub fn send_metadata<T: Bosun>(bosun: &T, registry: &MetricRegistry) -> Result<(), Error> {
+    for metadata in registry.metadata().iter().chain(metrics::METRICS.metadata().iter()) {
+        bosun.emit_metadata(metadata)?;
+    }
 
     Ok(())
 }
+
+/// Annotates a destructive action, e.g., terminating an instance, so it shows up on Bosun's and Grafana's graphs.
+///
+/// The annotation's source is the function's name. Failing to annotate is only logged, because the action has
+/// already been taken.
+pub fn annotate_action<T: Bosun>(bosun: &T, ctx: &Context, category: &str, host: Option<&str>, message: &str) {
+    let mut annotation = Annotation::new(ctx.function_name.as_str(), category, message);
+    if let Some(host) = host {
+        annotation = annotation.host(host);
+    }
+
+    match bosun.create_annotation(&annotation) {
+        Ok(annotation) => debug!("Created annotation '{}' for '{}'.", annotation.id, message),
+        Err(err) => warn!("Failed to annotate '{}' because {}.", message, err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use bosun::testing::{BosunMockClient, Call};
+    use spectral::prelude::*;
+
+    #[test]
+    fn monitoring_client_routes_metrics_to_sink() {
+        let bosun = BosunMockClient::default();
+        let sink = BosunMockClient::default();
+        let sink_stats = sink.stats.clone();
+        let client = MonitoringClient::new(bosun, Some(Box::new(sink)));
+
+        let tags = Tags::new();
+        let datum = Datum::now("a_test_metric", "42", &tags);
+        client.emit_datum(&datum).expect("failed to emit datum");
+        let silence = Silence::builder()
+            .host("webserver-*")
+            .duration("15m")
+            .user("lambda")
+            .build()
+            .expect("failed to build silence");
+        client.set_silence(&silence).expect("failed to set silence");
+
+        assert_that(&client.bosun.to_stats().datum_count).is_equal_to(0);
+        assert_that(&client.bosun.to_stats().set_silence_count).is_equal_to(1);
+        assert_that(&sink_stats.borrow()["datum"]).is_equal_to(1);
+    }
+
+    #[test]
+    fn annotate_action_does_not_fail() {
+        let bosun = BosunMockClient::default().fail_nth(
+            Call::CreateAnnotation,
+            2,
+            BosunError::Unavailable("503 Service Unavailable".to_string()),
+        );
+        let mut ctx = Context::default();
+        ctx.function_name = "aws-scaletower".to_string();
+
+        annotate_action(
+            &bosun,
+            &ctx,
+            "test.termination",
+            Some("i-123"),
+            "Terminated instance i-123",
+        );
+        annotate_action(
+            &bosun,
+            &ctx,
+            "test.termination",
+            Some("i-456"),
+            "Terminated instance i-456",
+        );
+
+        let annotations = bosun.annotations_for("test.termination");
+        assert_that(&annotations).has_length(1);
+        assert_that(&annotations[0].source.as_str()).is_equal_to("aws-scaletower");
+        assert_that(&annotations[0].host.as_str()).is_equal_to("i-123");
+        assert_that(&bosun.calls(Call::CreateAnnotation)).is_equal_to(2);
+    }
+}
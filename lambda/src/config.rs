@@ -6,7 +6,7 @@ use log::debug;
 use serde_derive::{Deserialize, Serialize};
 
 use crate::error::LambdaError;
-use aws::AwsClientConfig;
+use aws::{kms, AwsClientConfig};
 
 #[derive(Debug)]
 pub struct EnvConfig {
@@ -60,4 +60,149 @@ pub struct BosunConfig {
     pub password: String,
     pub timeout: Option<u64>,
     pub tags: HashMap<String, String>,
+    /// Number of retries after 5xx responses and timeouts; defaults to `bosun::RetryPolicy::default()`
+    pub retries: Option<u32>,
+    /// File to spool failed requests to and replay them from on the next invocation, e.g., `/tmp/bosun.spool`
+    pub spool_file: Option<String>,
+    /// Backend metric metadata and data are sent to; silences are always set in Bosun
+    #[serde(default)]
+    pub sink: MetricSinkConfig,
+    /// Timeout in sec for establishing a connection to Bosun
+    pub connect_timeout: Option<u64>,
+    /// Proxy for all requests to Bosun, e.g., `http://proxy.internal:3128`
+    pub proxy: Option<String>,
+    pub tls: Option<BosunTlsConfig>,
+}
+
+/// TLS options for Bosun; if set, a host without scheme is contacted via https.
+#[derive(PartialEq, Deserialize, Serialize, Debug, Clone, Default)]
+pub struct BosunTlsConfig {
+    /// PEM encoded CA bundle trusted in addition to the system's CAs
+    pub ca_file: Option<String>,
+    /// PKCS#12 encoded client certificate and key
+    pub client_identity_file: Option<String>,
+    /// Password of the client identity; KMS encrypted and base64 encoded
+    pub client_identity_password: Option<String>,
+}
+
+impl BosunTlsConfig {
+    /// Decrypts the client identity password.
+    pub fn decrypt(self, aws_client_config: &AwsClientConfig) -> Result<BosunTlsConfig, Error> {
+        let client_identity_password = match self.client_identity_password {
+            Some(ref password) => Some(kms::decrypt_base64(aws_client_config, password)?),
+            None => None,
+        };
+
+        Ok(BosunTlsConfig {
+            client_identity_password,
+            ..self
+        })
+    }
+}
+
+/// Selects the backend for metric metadata and data.
+#[derive(PartialEq, Deserialize, Serialize, Debug, Clone)]
+#[serde(tag = "kind")]
+pub enum MetricSinkConfig {
+    /// Send metrics to the Bosun host configured in `BosunConfig`.
+    #[serde(rename = "bosun")]
+    Bosun,
+    /// Send metrics directly to OpenTSDB at `host`, i.e., `<HOSTNAME|IP ADDR>:<PORT>` or a URL.
+    #[serde(rename = "opentsdb")]
+    OpenTsdb { host: String },
+    /// Push metrics to the Prometheus Pushgateway at `url` using `job` as grouping key.
+    #[serde(rename = "prometheus_pushgateway")]
+    PrometheusPushgateway { url: String, job: String },
+    /// Put metrics to CloudWatch in `namespace`.
+    #[serde(rename = "cloudwatch")]
+    CloudWatch { namespace: String },
+}
+
+impl Default for MetricSinkConfig {
+    fn default() -> Self {
+        MetricSinkConfig::Bosun
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use spectral::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn deserialize_bosun_config_without_sink() {
+        let toml = r#"host = 'localhost:8070'
+user = 'bosun'
+password = 'bosun'
+timeout = 5
+
+[tags]
+"#;
+
+        let config: Result<BosunConfig, _> = toml::from_str(toml);
+
+        asserting("bosun config loads successfully")
+            .that(&config)
+            .is_ok()
+            .map(|x| &x.sink)
+            .is_equal_to(&MetricSinkConfig::Bosun);
+    }
+
+    #[test]
+    fn deserialize_bosun_config_with_pushgateway_sink() {
+        let toml = r#"host = 'localhost:8070'
+user = 'bosun'
+password = 'bosun'
+timeout = 5
+
+[tags]
+
+[sink]
+kind = 'prometheus_pushgateway'
+url = 'http://localhost:9091'
+job = 'aws-watchtower'
+"#;
+        let expected = MetricSinkConfig::PrometheusPushgateway {
+            url: "http://localhost:9091".to_string(),
+            job: "aws-watchtower".to_string(),
+        };
+
+        let config: Result<BosunConfig, _> = toml::from_str(toml);
+
+        asserting("bosun config loads successfully")
+            .that(&config)
+            .is_ok()
+            .map(|x| &x.sink)
+            .is_equal_to(&expected);
+    }
+
+    #[test]
+    fn deserialize_bosun_config_with_proxy_and_tls() {
+        let toml = r#"host = 'bosun.internal:8070'
+user = 'bosun'
+password = 'bosun'
+timeout = 5
+connect_timeout = 1
+proxy = 'http://proxy.internal:3128'
+
+[tags]
+
+[tls]
+ca_file = 'internal-ca.pem'
+"#;
+        let expected = BosunTlsConfig {
+            ca_file: Some("internal-ca.pem".to_string()),
+            ..Default::default()
+        };
+
+        let config: Result<BosunConfig, _> = toml::from_str(toml);
+
+        asserting("bosun config loads successfully")
+            .that(&config)
+            .is_ok()
+            .map(|x| &x.tls)
+            .is_some()
+            .is_equal_to(&expected);
+    }
 }
@@ -6,4 +6,6 @@ pub enum LambdaError {
     FailedEnvVar(&'static str),
     #[fail(display = "failed to load config file because {}", _0)]
     FailedConfig(String),
+    #[fail(display = "failed to read file '{}'", _0)]
+    FailedReadFile(String),
 }
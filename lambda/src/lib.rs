@@ -2,10 +2,12 @@ use failure::Error;
 use lambda_runtime::Context;
 use serde_derive::Serialize;
 
+pub mod asg_mapping;
 pub mod bosun;
 pub mod config;
 pub mod error;
 pub mod metrics;
+pub mod sink;
 
 pub struct FunctionVersion {
     pub git_commit_sha: &'static str,
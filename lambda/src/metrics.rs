@@ -1,2 +1,21 @@
+use bosun::{registry::MetricRegistry, Rate, Unit};
+
 pub static LAMBDA_INVOCATION_COUNT: &str = "aws.lambda.function.invocation.count";
 pub static LAMBDA_INVOCATION_RESULT: &str = "aws.lambda.function.invocation.result";
+
+lazy_static::lazy_static! {
+    /// Metrics every function emits for its invocations.
+    pub static ref METRICS: MetricRegistry = MetricRegistry::new()
+        .register(
+            LAMBDA_INVOCATION_COUNT,
+            Rate::Rate,
+            Unit::Invocations,
+            "AWS Lambda function invocation counter",
+        )
+        .register(
+            LAMBDA_INVOCATION_RESULT,
+            Rate::Gauge,
+            Unit::Result,
+            "AWS Lambda function invocation result code [0 = success, >0 = failure]",
+        );
+}
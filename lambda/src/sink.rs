@@ -0,0 +1,79 @@
+use aws::{
+    cloudwatch::{self, MetricValue, MAX_DIMENSIONS_PER_METRIC},
+    AwsClientConfig,
+};
+use bosun::{sink::MetricSink, BosunError, BosunResult, Datum, Metadata, Tags};
+use chrono::{TimeZone, Utc};
+use log::{debug, info, warn};
+
+/// Puts metric data to CloudWatch; tags become dimensions and metadata is ignored.
+pub struct CloudWatchSink {
+    aws_client_config: AwsClientConfig,
+    pub namespace: String,
+    pub default_tags: Tags,
+}
+
+impl CloudWatchSink {
+    pub fn new(aws_client_config: &AwsClientConfig, namespace: &str, default_tags: Tags) -> CloudWatchSink {
+        CloudWatchSink {
+            aws_client_config: aws_client_config.clone(),
+            namespace: namespace.to_string(),
+            default_tags,
+        }
+    }
+
+    fn to_metric_value(&self, datum: &Datum) -> Option<MetricValue> {
+        let value = match datum.value.parse::<f64>() {
+            Ok(value) => value,
+            Err(_) => {
+                warn!(
+                    "Skipping datum for metric '{}' because value '{}' is not a number.",
+                    datum.metric, datum.value
+                );
+                return None;
+            }
+        };
+
+        let mut tags = self.default_tags.clone();
+        tags.extend(datum.tags.iter().map(|(k, v)| (k.clone(), v.clone())));
+        let mut dimensions: Vec<_> = tags.into_iter().collect();
+        // Sort to make sure the same dimensions are kept in case there are too many.
+        dimensions.sort();
+        if dimensions.len() > MAX_DIMENSIONS_PER_METRIC {
+            warn!(
+                "Metric '{}' has {} tags, but CloudWatch only supports {} dimensions.",
+                datum.metric,
+                dimensions.len(),
+                MAX_DIMENSIONS_PER_METRIC
+            );
+        }
+
+        Some(MetricValue {
+            name: datum.metric.to_string(),
+            timestamp: Utc.timestamp_millis(datum.timestamp),
+            value,
+            dimensions,
+        })
+    }
+}
+
+impl MetricSink for CloudWatchSink {
+    fn emit_metadata(&self, metadata: &Metadata) -> BosunResult {
+        debug!("Ignoring metadata for metric '{}' for CloudWatch.", metadata.metric);
+        Ok(())
+    }
+
+    fn emit_data(&self, data: &[Datum]) -> BosunResult {
+        let values: Vec<_> = data.iter().filter_map(|x| self.to_metric_value(x)).collect();
+        let res = cloudwatch::put_metric_data(&self.aws_client_config, &self.namespace, &values)
+            .map_err(|e| BosunError::EmitError(e.to_string()));
+        info!(
+            "Put {} data to CloudWatch namespace '{}' with result: '{:?}'.",
+            values.len(),
+            &self.namespace,
+            res
+        );
+
+        res
+    }
+}
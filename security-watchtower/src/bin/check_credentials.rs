@@ -7,7 +7,8 @@ use rusoto_core::Region;
 use aws::AwsClientConfig;
 use duo::DuoClient;
 use security_watchtower::check_credentials::{
-    check_aws_credentials, check_duo_credentials, Credential, IdentifyInactive, InactiveCredential, InactiveSpec,
+    check_aws_credentials, check_duo_credentials, Credential, CredentialKind, IdentifyInactive, InactiveCredential,
+    InactiveSpec,
 };
 
 fn main() {
@@ -29,7 +30,8 @@ fn main() {
     let aws_client_config = AwsClientConfig::with_region(Region::UsEast1).expect("Failed to create AWS client config");
 
     let mut credentials = check_duo_credentials(&duo_client).expect("Failed to get Duo credentials");
-    let aws_credentials = check_aws_credentials(&aws_client_config).expect("failed to load credentials");
+    let aws_credentials =
+        check_aws_credentials(&aws_client_config, &CredentialKind::aws()).expect("failed to load credentials");
     credentials.extend(aws_credentials);
 
     print_credentials(&credentials);
@@ -37,6 +39,7 @@ fn main() {
     let inactive_spec = InactiveSpec {
         disable_threshold_days: 60,
         delete_threshold_days: 180,
+        never_used_threshold_days: None,
     };
 
     let inactives = credentials.identify_inactive(&inactive_spec);
@@ -0,0 +1,158 @@
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+use failure::Error;
+use prettytable::{format, Cell, Row, Table};
+use structopt::StructOpt;
+
+use aws::AwsClientConfig;
+use security_watchtower::audit::{self, AuditOutcome, AuditQuery, AuditRecord, AuditStore};
+use security_watchtower::audit::{DynamoDbAuditStore, FileAuditStore, S3AuditStore};
+
+/// Queries the audit trail of security-watchtower
+#[derive(StructOpt, Debug)]
+#[structopt(name = "query-audit-security-watchtower")]
+struct Opt {
+    /// Reads audit records from this local file
+    #[structopt(long = "file", parse(from_os_str))]
+    file: Option<PathBuf>,
+
+    /// Reads audit records from this S3 bucket
+    #[structopt(long = "s3-bucket")]
+    s3_bucket: Option<String>,
+
+    /// Reads audit records below this prefix in the S3 bucket
+    #[structopt(long = "s3-prefix", default_value = "audit")]
+    s3_prefix: String,
+
+    /// Reads audit records from this DynamoDB table; requires --run-id or --credential-id
+    #[structopt(long = "dynamodb-table")]
+    dynamodb_table: Option<String>,
+
+    /// Only show records of this run, i.e., Lambda request id
+    #[structopt(long = "run-id")]
+    run_id: Option<String>,
+
+    /// Only show records of this credential id
+    #[structopt(long = "credential-id")]
+    credential_id: Option<String>,
+
+    /// Only show records of this user
+    #[structopt(long = "user-name")]
+    user_name: Option<String>,
+
+    /// Only show records with this outcome: evaluated, skipped, deferred, dry_run, applied, or failed
+    #[structopt(long = "outcome")]
+    outcome: Option<AuditOutcome>,
+
+    /// Only show records at or after this time, e.g., 2020-09-01T00:00:00Z
+    #[structopt(long = "since", parse(try_from_str = "parse_time"))]
+    since: Option<DateTime<Utc>>,
+
+    /// Only show records before this time, e.g., 2020-10-01T00:00:00Z
+    #[structopt(long = "until", parse(try_from_str = "parse_time"))]
+    until: Option<DateTime<Utc>>,
+
+    /// Prints records as JSON lines instead of a table
+    #[structopt(long = "json")]
+    json: bool,
+}
+
+fn parse_time(s: &str) -> Result<DateTime<Utc>, chrono::ParseError> {
+    DateTime::parse_from_rfc3339(s).map(|x| x.with_timezone(&Utc))
+}
+
+fn main() {
+    env_logger::init();
+    let args = Opt::from_args();
+
+    let store = store_from_args(&args);
+    let query = AuditQuery {
+        run_id: args.run_id,
+        credential_id: args.credential_id,
+        user_name: args.user_name,
+        outcome: args.outcome,
+        since: args.since,
+        until: args.until,
+    };
+    let records = audit::query(store.as_ref(), &query).expect("Failed to query audit trail");
+
+    if args.json {
+        print_json(&records).expect("Failed to print audit records");
+    } else {
+        print_records(&records);
+    }
+}
+
+fn store_from_args(args: &Opt) -> Box<dyn AuditStore> {
+    if let Some(ref path) = args.file {
+        return Box::new(FileAuditStore::new(path));
+    }
+
+    let aws_client_config = AwsClientConfig::new().expect("Failed to AWS client config.");
+    match (&args.s3_bucket, &args.dynamodb_table) {
+        (Some(bucket), None) => Box::new(S3AuditStore::new(
+            &aws_client_config,
+            bucket.clone(),
+            args.s3_prefix.clone(),
+        )),
+        (None, Some(table)) => Box::new(DynamoDbAuditStore::new(&aws_client_config, table.clone())),
+        _ => {
+            eprintln!("Please specify exactly one of --file, --s3-bucket, or --dynamodb-table.");
+            std::process::exit(1);
+        }
+    }
+}
+
+fn print_json(records: &[AuditRecord]) -> Result<(), Error> {
+    for record in records {
+        println!("{}", serde_json::to_string(record)?);
+    }
+
+    Ok(())
+}
+
+fn print_records(records: &[AuditRecord]) {
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+    table.set_titles(Row::new(vec![
+        Cell::new("Time"),
+        Cell::new("Actor"),
+        Cell::new("Service"),
+        Cell::new("User"),
+        Cell::new("Id"),
+        Cell::new("Credential Type"),
+        Cell::new("Prior State"),
+        Cell::new("Rule"),
+        Cell::new("Action"),
+        Cell::new("Outcome"),
+        Cell::new("Details"),
+    ]));
+
+    for record in records {
+        let row = record_to_row(record);
+        table.add_row(row);
+    }
+
+    table.printstd();
+}
+
+fn record_to_row(record: &AuditRecord) -> Row {
+    let time = record.timestamp.to_rfc3339();
+    let outcome = record.outcome.to_string();
+    let details = record.details.as_deref().unwrap_or("-");
+
+    Row::new(vec![
+        Cell::new(&time),
+        Cell::new(&record.actor),
+        Cell::new(&record.service),
+        Cell::new(&record.user_name),
+        Cell::new(&record.credential_id),
+        Cell::new(&record.kind),
+        Cell::new(&record.prior_state),
+        Cell::new(&record.rule),
+        Cell::new(&record.action),
+        Cell::new(&outcome),
+        Cell::new(details),
+    ])
+}
@@ -7,13 +7,16 @@ use serde_derive::{Deserialize, Serialize};
 
 use aws::{kms, AwsClientConfig};
 use duo::DuoClientConfig;
-use lambda::config::{BosunConfig, EncryptedConfig};
+use lambda::config::{BosunConfig, EncryptedConfig, MetricSinkConfig};
+
+use crate::check_credentials::CredentialKind;
 
 #[derive(Config, PartialEq, Deserialize, Serialize, Debug)]
 pub struct EncryptedFunctionConfig {
     pub bosun: BosunConfig,
     pub duo: DuoClientConfig,
     pub credentials: CredentialsConfig,
+    pub audit: Option<AuditConfig>,
 }
 
 impl EncryptedConfig<EncryptedFunctionConfig, FunctionConfig> for EncryptedFunctionConfig {
@@ -21,8 +24,11 @@ impl EncryptedConfig<EncryptedFunctionConfig, FunctionConfig> for EncryptedFunct
         let bosun_auth_password = kms::decrypt_base64(aws_client_config, &self.bosun.password)?;
         let duo_secret_key = kms::decrypt_base64(aws_client_config, &self.duo.secret_key)?;
 
+        let bosun_tls = self.bosun.tls.map(|tls| tls.decrypt(aws_client_config)).transpose()?;
+
         let bosun = BosunConfig {
             password: bosun_auth_password,
+            tls: bosun_tls,
             ..self.bosun
         };
 
@@ -31,10 +37,21 @@ impl EncryptedConfig<EncryptedFunctionConfig, FunctionConfig> for EncryptedFunct
             ..self.duo
         };
 
+        let notification = self
+            .credentials
+            .notification
+            .map(|notification| notification.decrypt(aws_client_config))
+            .transpose()?;
+        let credentials = CredentialsConfig {
+            notification,
+            ..self.credentials
+        };
+
         let config = FunctionConfig {
             bosun,
             duo,
-            credentials: self.credentials,
+            credentials,
+            audit: self.audit,
         };
 
         Ok(config)
@@ -46,6 +63,7 @@ pub struct FunctionConfig {
     pub bosun: BosunConfig,
     pub duo: DuoClientConfig,
     pub credentials: CredentialsConfig,
+    pub audit: Option<AuditConfig>,
 }
 
 impl FunctionConfig {}
@@ -58,12 +76,21 @@ impl Default for FunctionConfig {
             password: "bosun".to_string(),
             timeout: Some(5),
             tags: HashMap::new(),
+            retries: None,
+            spool_file: None,
+            sink: MetricSinkConfig::Bosun,
+            connect_timeout: None,
+            proxy: None,
+            tls: None,
         };
 
         let duo = DuoClientConfig {
             api_host_name: "apixxxxx.duo.com".to_string(),
             integration_key: "123456789ABCDEF".to_string(),
             secret_key: "WouldYouWant2Know?".to_string(),
+            retries: None,
+            max_backoff_sec: None,
+            signature_version: None,
         };
 
         let credentials = CredentialsConfig {
@@ -71,12 +98,17 @@ impl Default for FunctionConfig {
             delete_threshold_days: 180,
             actions_enabled: false,
             whitelist: HashSet::new(),
+            never_used_threshold_days: None,
+            aws_kinds: None,
+            rotation: None,
+            notification: None,
         };
 
         FunctionConfig {
             bosun,
             duo,
             credentials,
+            audit: None,
         }
     }
 }
@@ -87,6 +119,104 @@ pub struct CredentialsConfig {
     pub delete_threshold_days: i64,
     pub actions_enabled: bool,
     pub whitelist: HashSet<String>,
+    /// Disables credentials that have never been used this many days after their creation
+    pub never_used_threshold_days: Option<i64>,
+    /// Kinds of IAM credentials to check; passwords and API keys if unset
+    #[serde(default)]
+    pub aws_kinds: Option<HashSet<CredentialKind>>,
+    pub rotation: Option<RotationConfig>,
+    pub notification: Option<NotificationConfig>,
+}
+
+impl CredentialsConfig {
+    pub fn aws_kinds(&self) -> HashSet<CredentialKind> {
+        self.aws_kinds.clone().unwrap_or_else(CredentialKind::aws_default)
+    }
+}
+
+/// Maximum age of AWS access keys, SSH public keys, signing certificates, and service-specific credentials before they
+/// have to be rotated
+#[derive(PartialEq, Deserialize, Serialize, Debug)]
+pub struct RotationConfig {
+    pub warn_threshold_days: i64,
+    pub disable_threshold_days: i64,
+}
+
+/// Advance notices to credential owners before inactive credentials are disabled or deleted
+#[derive(PartialEq, Deserialize, Serialize, Debug)]
+pub struct NotificationConfig {
+    /// Notify owners this many days before a credential crosses an inactivity threshold
+    pub notice_period_days: i64,
+    /// Apply actions only this many days after the first notice
+    pub grace_period_days: i64,
+    pub notifier: NotifierConfig,
+    pub store: NoticeStoreConfig,
+}
+
+impl NotificationConfig {
+    fn decrypt(self, aws_client_config: &AwsClientConfig) -> Result<NotificationConfig, Error> {
+        let notifier = match self.notifier {
+            NotifierConfig::Slack { webhook_url } => NotifierConfig::Slack {
+                webhook_url: kms::decrypt_base64(aws_client_config, &webhook_url)?,
+            },
+            notifier => notifier,
+        };
+
+        Ok(NotificationConfig { notifier, ..self })
+    }
+}
+
+/// Selects how credential owners are notified.
+#[derive(PartialEq, Deserialize, Serialize, Debug)]
+#[serde(tag = "kind")]
+pub enum NotifierConfig {
+    /// Email owners from `source`; owners without a known address are mailed at `<user name>@<recipient_domain>`
+    /// or at `fallback_recipient`.
+    #[serde(rename = "ses")]
+    Ses {
+        source: String,
+        recipient_domain: Option<String>,
+        fallback_recipient: Option<String>,
+    },
+    /// Publish notices to the SNS topic `topic_arn`.
+    #[serde(rename = "sns")]
+    Sns { topic_arn: String },
+    /// Post notices to the Slack incoming webhook `webhook_url`; the URL is encrypted.
+    #[serde(rename = "slack")]
+    Slack { webhook_url: String },
+}
+
+/// Selects where the dates of the first notices are persisted between runs.
+#[derive(PartialEq, Deserialize, Serialize, Debug)]
+#[serde(tag = "kind")]
+pub enum NoticeStoreConfig {
+    /// Store notices in the S3 object `key` in `bucket`.
+    #[serde(rename = "s3")]
+    S3 { bucket: String, key: String },
+    /// Store notices in the local file `path`.
+    #[serde(rename = "file")]
+    File { path: String },
+}
+
+/// Audit trail of all evaluated and applied actions
+#[derive(PartialEq, Deserialize, Serialize, Debug)]
+pub struct AuditConfig {
+    pub store: AuditStoreConfig,
+}
+
+/// Selects where audit records are written to.
+#[derive(PartialEq, Deserialize, Serialize, Debug)]
+#[serde(tag = "kind")]
+pub enum AuditStoreConfig {
+    /// Write the records of each run to an S3 object below `prefix` in `bucket`.
+    #[serde(rename = "s3")]
+    S3 { bucket: String, prefix: String },
+    /// Put records into the DynamoDB table `table`.
+    #[serde(rename = "dynamodb")]
+    DynamoDb { table: String },
+    /// Append records to the local file `path`.
+    #[serde(rename = "file")]
+    File { path: String },
 }
 
 #[cfg(test)]
@@ -128,4 +258,113 @@ whitelist = []
             .is_ok()
             .is_equal_to(&expected);
     }
+
+    #[test]
+    fn deserialize_credentials_config_with_rotation() {
+        let toml = r#"disable_threshold_days = 60
+delete_threshold_days = 180
+actions_enabled = false
+whitelist = []
+
+[rotation]
+warn_threshold_days = 80
+disable_threshold_days = 90
+"#;
+        let config: Result<CredentialsConfig, _> = toml::from_str(toml);
+
+        asserting("credentials config with rotation loads successfully")
+            .that(&config)
+            .is_ok()
+            .map(|x| &x.rotation)
+            .is_equal_to(Some(RotationConfig {
+                warn_threshold_days: 80,
+                disable_threshold_days: 90,
+            }));
+    }
+
+    #[test]
+    fn deserialize_credentials_config_with_aws_kinds() {
+        let toml = r#"disable_threshold_days = 60
+delete_threshold_days = 180
+actions_enabled = false
+whitelist = []
+aws_kinds = ["api_key", "signing_cert"]
+"#;
+        let config: CredentialsConfig = toml::from_str(toml).expect("Failed to load credentials config");
+
+        let expected: HashSet<_> = vec![CredentialKind::ApiKey, CredentialKind::SigningCertificate]
+            .into_iter()
+            .collect();
+        assert_that(&config.aws_kinds()).is_equal_to(expected);
+    }
+
+    #[test]
+    fn check_passwords_and_api_keys_by_default() {
+        let config = FunctionConfig::default();
+
+        let expected: HashSet<_> = vec![CredentialKind::Password, CredentialKind::ApiKey]
+            .into_iter()
+            .collect();
+        assert_that(&config.credentials.aws_kinds()).is_equal_to(expected);
+    }
+
+    #[test]
+    fn deserialize_credentials_config_with_notification() {
+        let toml = r#"disable_threshold_days = 60
+delete_threshold_days = 180
+actions_enabled = false
+whitelist = []
+
+[notification]
+notice_period_days = 14
+grace_period_days = 7
+
+[notification.notifier]
+kind = "ses"
+source = "security@example.com"
+recipient_domain = "example.com"
+
+[notification.store]
+kind = "s3"
+bucket = "security-watchtower"
+key = "notices.json"
+"#;
+        let config: Result<CredentialsConfig, _> = toml::from_str(toml);
+
+        asserting("credentials config with notification loads successfully")
+            .that(&config)
+            .is_ok()
+            .map(|x| &x.notification)
+            .is_equal_to(Some(NotificationConfig {
+                notice_period_days: 14,
+                grace_period_days: 7,
+                notifier: NotifierConfig::Ses {
+                    source: "security@example.com".to_string(),
+                    recipient_domain: Some("example.com".to_string()),
+                    fallback_recipient: None,
+                },
+                store: NoticeStoreConfig::S3 {
+                    bucket: "security-watchtower".to_string(),
+                    key: "notices.json".to_string(),
+                },
+            }));
+    }
+
+    #[test]
+    fn deserialize_audit_config() {
+        let toml = r#"[store]
+kind = "dynamodb"
+table = "security-watchtower-audit"
+"#;
+        let config: Result<AuditConfig, _> = toml::from_str(toml);
+
+        asserting("audit config loads successfully")
+            .that(&config)
+            .is_ok()
+            .is_equal_to(AuditConfig {
+                store: AuditStoreConfig::DynamoDb {
+                    table: "security-watchtower-audit".to_string(),
+                },
+            });
+    }
 }
@@ -1,9 +1,9 @@
 use crate::{config::FunctionConfig, error::AwsScaleTowerError};
 use aws::AwsClientConfig;
-use bosun::{Bosun, Datum, Tags};
+use bosun::{Bosun, Tags};
 use failure::{Error, Fail};
 use lambda_runtime::Context;
-use log::debug;
+use log::{debug, warn};
 use serde_derive::{Deserialize, Serialize};
 use serde_json::{self, Value};
 
@@ -39,20 +39,21 @@ pub fn handle<T: Bosun>(
     bosun: &T,
 ) -> Result<HandleResult, Error> {
     let tags = Tags::new();
-    let datum = Datum::now(lambda::metrics::LAMBDA_INVOCATION_COUNT, "1", &tags);
-    bosun.emit_datum(&datum)?;
+    let datum = lambda::metrics::METRICS.datum_now(lambda::metrics::LAMBDA_INVOCATION_COUNT, "1", &tags);
+    // A monitoring outage must not keep us from handling the event.
+    if let Err(err) = datum.and_then(|datum| bosun.emit_datum(&datum)) {
+        warn!("Failed to emit invocation count because {}.", err);
+    }
 
     let res = parse_event(json).and_then(|event| handle_event(aws_client_config, event, ctx, &config, bosun));
 
-    match res {
-        Ok(_) => {
-            let datum = Datum::now(lambda::metrics::LAMBDA_INVOCATION_RESULT, "0", &tags);
-            bosun.emit_datum(&datum)?
-        }
-        Err(_) => {
-            let datum = Datum::now(lambda::metrics::LAMBDA_INVOCATION_RESULT, "1", &tags);
-            bosun.emit_datum(&datum)?
-        }
+    let value = match res {
+        Ok(_) => "0",
+        Err(_) => "1",
+    };
+    let datum = lambda::metrics::METRICS.datum_now(lambda::metrics::LAMBDA_INVOCATION_RESULT, value, &tags);
+    if let Err(err) = datum.and_then(|datum| bosun.emit_datum(&datum)) {
+        warn!("Failed to emit invocation result because {}.", err);
     }
 
     res
@@ -7,11 +7,13 @@ use log::{debug, info};
 use serde_json::Value;
 use std::sync::atomic::{AtomicUsize, Ordering};
 
+pub mod audit;
 pub mod check_credentials;
 pub mod config;
 pub mod error;
 pub mod events;
 pub mod metrics;
+pub mod notification;
 
 static FUNCTION_VERSION: lambda::FunctionVersion = FunctionVersion {
     git_commit_sha: env!("VERGEN_SHA_SHORT"),
@@ -48,7 +50,8 @@ fn run(json: Value, ctx: &Context) -> Result<(), Error> {
     }
 
     // Run per each invocation
-    let bosun = lambda::bosun::init(&CONFIG.bosun, ctx).map_err(|e| ctx.new_error(e.to_string().as_str()))?;
+    let bosun = lambda::bosun::init(&CONFIG.bosun, &AWS_CLIENT_CONFIG, ctx)
+        .map_err(|e| ctx.new_error(e.to_string().as_str()))?;
 
     // Only run once per instance of lambda function
     if invocation_counter == 0 {
@@ -1,25 +1,39 @@
-use bosun::{Bosun, Metadata};
+use bosun::{registry::MetricRegistry, Bosun, Rate, Unit};
 use failure::Error;
 
 pub static CREDENTIAL_LAST_USAGE: &str = "security.credentials.last_usage";
+pub static CREDENTIAL_ROTATION_AGE: &str = "security.credentials.rotation_age";
+pub static CREDENTIAL_NEVER_USED_AGE: &str = "security.credentials.never_used_age";
 pub static SCHEDULED_EVENT: &str = "aws.events.scheduled_event";
 
-pub fn send_metadata<T: Bosun>(bosun: &T) -> Result<(), Error> {
-    let metadatas = bosun_metadata();
-    lambda::bosun::send_metadata(bosun, &metadatas)
+lazy_static::lazy_static! {
+    pub static ref METRICS: MetricRegistry = MetricRegistry::new()
+        .register(
+            CREDENTIAL_LAST_USAGE,
+            Rate::Gauge,
+            Unit::Days,
+            "Number of days a credential has been used for the last time; -1 is used, if unknown",
+        )
+        .register(
+            CREDENTIAL_ROTATION_AGE,
+            Rate::Gauge,
+            Unit::Days,
+            "Number of days since a credential has been created or rotated for the last time",
+        )
+        .register(
+            CREDENTIAL_NEVER_USED_AGE,
+            Rate::Gauge,
+            Unit::Days,
+            "Number of days since a credential that has never been used has been created",
+        )
+        .register(
+            SCHEDULED_EVENT,
+            Rate::Gauge,
+            Unit::Event,
+            "AWS schedule event",
+        );
 }
 
-fn bosun_metadata() -> Vec<Metadata<'static>> {
-    let mut metadatas = Vec::new();
-
-    metadatas.push(Metadata::new(
-        CREDENTIAL_LAST_USAGE,
-        "gauge",
-        "Days",
-        "Number of days a credential has been used for the last time; -1 is used, if unknown",
-    ));
-
-    metadatas.push(Metadata::new(SCHEDULED_EVENT, "gauge", "Event", "AWS schedule event"));
-
-    metadatas
+pub fn send_metadata<T: Bosun>(bosun: &T) -> Result<(), Error> {
+    lambda::bosun::send_metadata(bosun, &METRICS)
 }
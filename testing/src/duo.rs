@@ -0,0 +1,455 @@
+//! An in-process stand-in for the Duo Admin API.
+//!
+//! The server listens on a random local port, checks the signature of every request like Duo does, and serves users
+//! and phones from fixtures. Updating and deleting users and deleting phones change its state, and failures, e.g.,
+//! rate limiting, may be injected for the next requests. Point a `DuoClient` at `DuoServer::url`.
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use ring::{digest, hmac};
+use serde_json::{json, Value};
+
+/// Number of objects per page if a request does not specify a limit
+pub const DEFAULT_LIMIT: usize = 100;
+
+/// A request the server received
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuoRequest {
+    pub method: String,
+    pub path: String,
+    /// Decoded query parameters sorted by name
+    pub params: Vec<(String, String)>,
+    /// `true` if the signature matched either the v2 or the v5 canonicalization
+    pub authenticated: bool,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    users: Vec<Value>,
+    phones: Vec<Value>,
+    failures: VecDeque<u16>,
+    requests: Vec<DuoRequest>,
+}
+
+/// Handle of a running stand-in server; the server stops when the handle is dropped.
+pub struct DuoServer {
+    addr: SocketAddr,
+    integration_key: String,
+    secret_key: String,
+    state: Arc<Mutex<State>>,
+    shutdown: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl DuoServer {
+    /// Starts a server that accepts requests signed with `integration_key` and `secret_key`.
+    pub fn start<S: Into<String>, T: Into<String>>(integration_key: S, secret_key: T) -> io::Result<DuoServer> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let integration_key = integration_key.into();
+        let secret_key = secret_key.into();
+        let state = Arc::new(Mutex::new(State::default()));
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let handle = {
+            let keys = (integration_key.clone(), secret_key.clone());
+            let state = state.clone();
+            let shutdown = shutdown.clone();
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if shutdown.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    if let Ok(stream) = stream {
+                        let _ = handle_connection(stream, &keys.0, &keys.1, &state);
+                    }
+                }
+            })
+        };
+
+        Ok(DuoServer {
+            addr,
+            integration_key,
+            secret_key,
+            state,
+            shutdown,
+            handle: Some(handle),
+        })
+    }
+
+    /// Base URL to use as API host name of a `DuoClient`, e.g., `http://127.0.0.1:34567`
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    pub fn integration_key(&self) -> &str {
+        &self.integration_key
+    }
+
+    pub fn secret_key(&self) -> &str {
+        &self.secret_key
+    }
+
+    pub fn add_user(&self, user: Value) {
+        self.state().users.push(user);
+    }
+
+    pub fn add_phone(&self, phone: Value) {
+        self.state().phones.push(phone);
+    }
+
+    /// Current users including all changes made by requests
+    pub fn users(&self) -> Vec<Value> {
+        self.state().users.clone()
+    }
+
+    /// Current phones including all changes made by requests
+    pub fn phones(&self) -> Vec<Value> {
+        self.state().phones.clone()
+    }
+
+    /// Answers the next request with `status`, e.g., 429 for rate limiting or 500; failures queue up.
+    pub fn fail_next(&self, status: u16) {
+        self.state().failures.push_back(status);
+    }
+
+    /// All requests received so far
+    pub fn requests(&self) -> Vec<DuoRequest> {
+        self.state().requests.clone()
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().expect("Duo server state is poisoned")
+    }
+}
+
+impl Drop for DuoServer {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        // Wake up the accepting thread so it notices the shutdown.
+        let _ = TcpStream::connect(self.addr);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Integration key the server started by `stand_in_server` expects
+pub const INTEGRATION_KEY: &str = "DIWJ8X6AEYOR5OMC6TQ1";
+/// Secret key the server started by `stand_in_server` expects
+pub const SECRET_KEY: &str = "Zh5eGmUq9zpfQnyUIu5OL9iWoMMv5ZNmk3zLJ4Ep";
+
+/// Starts a server for `INTEGRATION_KEY` and `SECRET_KEY` that knows `users`, e.g., created by `user_fixture`.
+pub fn stand_in_server(users: Vec<Value>) -> DuoServer {
+    let server = DuoServer::start(INTEGRATION_KEY, SECRET_KEY).expect("Failed to start Duo server");
+    for user in users {
+        server.add_user(user);
+    }
+
+    server
+}
+
+/// User fixture as returned by `/admin/v1/users`; `last_login` is a Unix timestamp in seconds. All fixture users have
+/// been created on 2017-01-01.
+pub fn user_fixture(user_id: &str, username: &str, last_login: Option<i64>) -> Value {
+    json!({
+        "user_id": user_id,
+        "username": username,
+        "realname": username,
+        "email": format!("{}@example.com", username),
+        "is_enrolled": true,
+        "status": "active",
+        "last_login": last_login,
+        "created": 1_483_228_800,
+        "groups": [],
+    })
+}
+
+/// Phone fixture as returned by `/admin/v1/phones` belonging to user `user_id`
+pub fn phone_fixture(phone_id: &str, user_id: &str, last_seen: Option<&str>) -> Value {
+    json!({
+        "phone_id": phone_id,
+        "number": "+15555550100",
+        "name": "",
+        "type": "mobile",
+        "platform": "Google Android",
+        "activated": true,
+        "last_seen": last_seen.unwrap_or(""),
+        "users": [{"user_id": user_id, "username": user_id}],
+    })
+}
+
+struct HttpRequest {
+    method: String,
+    path: String,
+    query: String,
+    headers: HashMap<String, String>,
+    body: String,
+}
+
+fn handle_connection(
+    stream: TcpStream,
+    integration_key: &str,
+    secret_key: &str,
+    state: &Mutex<State>,
+) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let request = read_request(&mut reader)?;
+    let (status, body) = {
+        let mut state = state.lock().expect("Duo server state is poisoned");
+        respond(&request, integration_key, secret_key, &mut state)
+    };
+    write_response(stream, status, &body)
+}
+
+fn read_request<R: BufRead>(reader: &mut R) -> io::Result<HttpRequest> {
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let target = parts.next().unwrap_or_default();
+    let (path, query) = match target.find('?') {
+        Some(pos) => (target[..pos].to_string(), target[pos + 1..].to_string()),
+        None => (target.to_string(), String::new()),
+    };
+
+    let mut headers = HashMap::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some(pos) = line.find(':') {
+            headers.insert(line[..pos].trim().to_lowercase(), line[pos + 1..].trim().to_string());
+        }
+    }
+
+    let length = headers
+        .get("content-length")
+        .and_then(|x| x.parse::<usize>().ok())
+        .unwrap_or(0);
+    let mut body = vec![0u8; length];
+    reader.read_exact(&mut body)?;
+
+    Ok(HttpRequest {
+        method,
+        path,
+        query,
+        headers,
+        body: String::from_utf8_lossy(&body).to_string(),
+    })
+}
+
+fn write_response(mut stream: TcpStream, status: u16, body: &Value) -> io::Result<()> {
+    let body = body.to_string();
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason(status),
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes())?;
+    stream.flush()
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => "Unknown",
+    }
+}
+
+fn respond(request: &HttpRequest, integration_key: &str, secret_key: &str, state: &mut State) -> (u16, Value) {
+    let mut params: Vec<(String, String)> = url::form_urlencoded::parse(request.query.as_bytes())
+        .into_owned()
+        .collect();
+    params.sort();
+    let authenticated = is_authenticated(request, &params, integration_key, secret_key);
+    state.requests.push(DuoRequest {
+        method: request.method.clone(),
+        path: request.path.clone(),
+        params: params.clone(),
+        authenticated,
+    });
+
+    if let Some(status) = state.failures.pop_front() {
+        return (status, fail(status as usize * 100 + 1, reason(status)));
+    }
+    if !authenticated {
+        return (401, fail(40103, "Invalid signature in request credentials"));
+    }
+
+    let param = |name: &str| params.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str());
+    let segments: Vec<_> = request.path.trim_start_matches('/').split('/').collect();
+    match (request.method.as_str(), segments.as_slice()) {
+        ("GET", ["admin", "v1", "users"]) => {
+            let users: Vec<_> = state
+                .users
+                .iter()
+                .filter(|x| has_field(x, "username", param("username")))
+                .filter(|x| has_field(x, "email", param("email")))
+                .cloned()
+                .collect();
+            page(&users, param("limit"), param("offset"))
+        }
+        ("POST", ["admin", "v1", "users", user_id]) => {
+            match state.users.iter_mut().find(|x| x["user_id"] == *user_id) {
+                Some(user) => {
+                    for field in &[
+                        "username",
+                        "realname",
+                        "firstname",
+                        "lastname",
+                        "email",
+                        "notes",
+                        "status",
+                    ] {
+                        if let Some(value) = param(field) {
+                            user[*field] = json!(value);
+                        }
+                    }
+                    (200, ok(user.clone()))
+                }
+                None => not_found(),
+            }
+        }
+        ("POST", ["admin", "v1", "users", user_id, "bypass_codes"]) => {
+            if !state.users.iter().any(|x| x["user_id"] == *user_id) {
+                return not_found();
+            }
+            let count = param("count").and_then(|x| x.parse().ok()).unwrap_or(10usize);
+            let codes: Vec<_> = (0..count).map(|x| format!("{:09}", 123_456_780 + x)).collect();
+            (200, ok(json!(codes)))
+        }
+        ("DELETE", ["admin", "v1", "users", user_id]) => {
+            state.users.retain(|x| x["user_id"] != *user_id);
+            (200, ok(Value::String(String::new())))
+        }
+        ("GET", ["admin", "v1", "phones"]) => page(&state.phones, param("limit"), param("offset")),
+        ("DELETE", ["admin", "v1", "phones", phone_id]) => {
+            state.phones.retain(|x| x["phone_id"] != *phone_id);
+            (200, ok(Value::String(String::new())))
+        }
+        _ => not_found(),
+    }
+}
+
+/// Returns `true` if no filter `value` is given or `object`'s `field` equals it.
+fn has_field(object: &Value, field: &str, value: Option<&str>) -> bool {
+    match value {
+        Some(value) => object[field] == value,
+        None => true,
+    }
+}
+
+fn page(objects: &[Value], limit: Option<&str>, offset: Option<&str>) -> (u16, Value) {
+    let limit = limit.and_then(|x| x.parse().ok()).unwrap_or(DEFAULT_LIMIT);
+    let offset = offset.and_then(|x| x.parse().ok()).unwrap_or(0usize).min(objects.len());
+    let end = (offset + limit).min(objects.len());
+    let mut metadata = json!({ "total_objects": objects.len() });
+    if offset > 0 {
+        metadata["prev_offset"] = json!(offset.saturating_sub(limit));
+    }
+    if end < objects.len() {
+        metadata["next_offset"] = json!(end);
+    }
+
+    let body = json!({
+        "stat": "OK",
+        "response": &objects[offset..end],
+        "metadata": metadata,
+    });
+    (200, body)
+}
+
+fn ok(response: Value) -> Value {
+    json!({ "stat": "OK", "response": response })
+}
+
+fn fail(code: usize, message: &str) -> Value {
+    json!({ "stat": "FAIL", "code": code, "message": message, "message_detail": "" })
+}
+
+fn not_found() -> (u16, Value) {
+    (404, fail(40401, "Resource not found"))
+}
+
+/// Checks the `Authorization` header against the v2 and the v5 canonicalization of the request.
+fn is_authenticated(
+    request: &HttpRequest,
+    params: &[(String, String)],
+    integration_key: &str,
+    secret_key: &str,
+) -> bool {
+    let (date, host, authorization) = match (
+        request.headers.get("date"),
+        request.headers.get("host"),
+        request.headers.get("authorization"),
+    ) {
+        (Some(date), Some(host), Some(authorization)) => (date, host, authorization),
+        _ => return false,
+    };
+    if !authorization.starts_with("Basic ") {
+        return false;
+    }
+    let credentials = match base64::decode(&authorization["Basic ".len()..])
+        .ok()
+        .and_then(|x| String::from_utf8(x).ok())
+    {
+        Some(credentials) => credentials,
+        None => return false,
+    };
+
+    let mut encoder = url::form_urlencoded::Serializer::new(String::new());
+    for (k, v) in params {
+        encoder.append_pair(k, v);
+    }
+    let params = encoder.finish();
+    let host = host.to_lowercase();
+    let canon = vec![
+        date.to_string(),
+        request.method.to_uppercase(),
+        host,
+        request.path.clone(),
+        params,
+    ];
+
+    let mut x_duo_headers: Vec<_> = request
+        .headers
+        .iter()
+        .filter(|(k, _)| k.starts_with("x-duo-"))
+        .collect();
+    x_duo_headers.sort();
+    let x_duo_headers: Vec<&str> = x_duo_headers
+        .into_iter()
+        .flat_map(|(k, v)| vec![k.as_str(), v.as_str()])
+        .collect();
+    let mut canon_v5 = canon.clone();
+    canon_v5.push(sha512_hex(&request.body));
+    canon_v5.push(sha512_hex(&x_duo_headers.join("\0")));
+
+    let v2 = sign(hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, secret_key, &canon);
+    let v5 = sign(hmac::HMAC_SHA512, secret_key, &canon_v5);
+
+    credentials == format!("{}:{}", integration_key, v2) || credentials == format!("{}:{}", integration_key, v5)
+}
+
+fn sha512_hex(data: &str) -> String {
+    hex::encode(digest::digest(&digest::SHA512, data.as_bytes()).as_ref())
+}
+
+fn sign(algorithm: hmac::Algorithm, secret_key: &str, canon: &[String]) -> String {
+    let key = hmac::Key::new(algorithm, secret_key.as_bytes());
+    hex::encode(hmac::sign(&key, canon.join("\n").as_bytes()).as_ref())
+}
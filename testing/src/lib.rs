@@ -1,5 +1,9 @@
+use std::fs;
+use std::path::PathBuf;
 use std::sync::Once;
 
+pub mod duo;
+
 pub static INIT: Once = Once::new();
 
 /// Setup function that is only run once, even if called multiple times.
@@ -8,3 +12,11 @@ pub fn setup() {
         env_logger::init();
     });
 }
+
+/// Returns a path named after `name` and the current process in the temp dir and removes any file left at this path.
+pub fn temp_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+    let _ = fs::remove_file(&path);
+
+    path
+}